@@ -1,5 +1,4 @@
 use anyhow::Result;
-use serde_yaml;
 use webql::vendor::github::{data::Config, events::GitHub};
 
 const CONFIG: &str = r#"
@@ -27,6 +27,7 @@ fn main() {
             query: r#""user"."login""#.to_string(),
             operation: Operation::Equal,
             values: vec!["kaplanelad".to_string()],
+            ..Filter::default()
         },
         // extract `https://github.com/rusty-ferris-club/webql` value from url json key and check if the value equal to
         // one of the given filter values
@@ -34,6 +35,7 @@ fn main() {
             query: r#""url""#.to_string(),
             operation: Operation::Equal,
             values: vec!["https://github.com/rusty-ferris-club/webql".to_string()],
+            ..Filter::default()
         },
         // extract `[label-1, label-2]` values from labels array and get all name values. check if
         // one of the values filter is equal to one of the name values one of the given
@@ -42,11 +44,13 @@ fn main() {
             query: r#""labels"|={"name"}."name""#.to_string(),
             operation: Operation::Equal,
             values: vec!["label-1".to_string()],
+            ..Filter::default()
         },
         Filter {
             query: r#""body""#.to_string(),
             operation: Operation::Contains,
             values: vec!["example".to_string()],
+            ..Filter::default()
         },
     ];
 
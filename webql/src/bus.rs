@@ -0,0 +1,142 @@
+//! In-process event bus
+//!
+//! Multiple components of the same process can subscribe to the subset of
+//! events they care about and receive them from a single poll, instead of each
+//! calling `get_events` separately.
+use std::sync::{
+    mpsc::{channel, Receiver, Sender},
+    Mutex, PoisonError,
+};
+
+use tracing::debug;
+
+use crate::{
+    data::{Event, EventKind, Filter},
+    jfilter,
+};
+
+/// Describe which events a subscriber receives
+#[derive(Debug, Clone, Default)]
+pub struct Subscription {
+    /// Event kinds to receive. empty means all kinds
+    pub kinds: Vec<EventKind>,
+    /// Filters on the event row data. empty means all events
+    pub filters: Vec<Filter>,
+}
+
+impl Subscription {
+    /// Check if the event should be sent to the subscriber. events that
+    /// failed the filters evaluation are not sent
+    fn is_match(&self, event: &Event) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind) {
+            return false;
+        }
+        match jfilter::is_match_filters(&event.row_data, &self.filters) {
+            Ok(is_match) => is_match,
+            Err(e) => {
+                debug!(
+                    message = "could not run subscription filters",
+                    event_id = event.id,
+                    err = e.to_string(),
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Publish events to subscribers
+#[derive(Debug, Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<(Subscription, Sender<Event>)>>,
+}
+
+impl EventBus {
+    /// Subscribe to the events that match the given [`Subscription`]
+    pub fn subscribe(&self, subscription: Subscription) -> Receiver<Event> {
+        let (sender, receiver) = channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((subscription, sender));
+        receiver
+    }
+
+    /// Send the events to all the matching subscribers. subscribers that
+    /// dropped their receiver are removed.
+    pub fn publish(&self, events: &[Event]) {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        subscribers.retain(|(subscription, sender)| {
+            events
+                .iter()
+                .filter(|event| subscription.is_match(event))
+                .all(|event| sender.send(event.clone()).is_ok())
+        });
+    }
+
+    /// Number of active subscribers
+    pub fn subscribers_count(&self) -> usize {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
+
+#[cfg(test)]
+mod test_bus {
+
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::{EventBus, Subscription};
+    use crate::data::{Event, EventKind, Filter, Operation};
+
+    fn event(id: &str, login: &str) -> Event {
+        Event {
+            kind: EventKind::Edited,
+            id: id.to_string(),
+            parent_event_id: None,
+            name: String::new(),
+            link: None,
+            date: None,
+            priority: 1,
+            row_data: json!({ "user": { "login": login } }),
+            annotations: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn can_publish_to_subscribers() {
+        let bus = EventBus::default();
+        let all = bus.subscribe(Subscription {
+            kinds: vec![EventKind::Edited],
+            ..Subscription::default()
+        });
+        let mine = bus.subscribe(Subscription {
+            filters: vec![Filter {
+                query: "user.login".to_string(),
+                values: vec!["kaplanelad".to_string()],
+                operation: Operation::Equal,
+                ..Filter::default()
+            }],
+            ..Subscription::default()
+        });
+
+        bus.publish(&[event("1", "kaplanelad"), event("2", "dependabot")]);
+
+        assert_eq!(all.try_iter().count(), 2);
+        assert_eq!(
+            mine.try_iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec!["1".to_string()]
+        );
+
+        drop(all);
+        bus.publish(&[event("3", "kaplanelad")]);
+        assert_eq!(bus.subscribers_count(), 1);
+    }
+}
@@ -1,17 +1,112 @@
 //! Public structs
-use chrono::{DateTime, Utc};
-use serde_derive::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+    time,
+};
+
+use anyhow::{bail, Error};
+use chrono::{DateTime, Duration, Utc};
+use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::utils;
+
+/// Default minimum similarity for [`Operation::Fuzzy`]
+pub const DEFAULT_FUZZY_THRESHOLD: f64 = 0.9;
+/// Default [`Limits::max_depth`]
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+/// Default [`Limits::max_size`], 64MB
+pub const DEFAULT_MAX_SIZE: usize = 64 * 1024 * 1024;
+
 /// Describe the data kind that fetched from the one of the vendors.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EventKind {
+    /// The body of a previously seen event was changed, see [`crate::edits`]
+    Edited,
+    /// Synthetic event of a threshold rule, see
+    /// [`crate::pipeline::threshold_alerts`]
+    Alert,
     #[cfg(feature = "github")]
     PR,
     #[cfg(feature = "github")]
     PrComment,
     #[cfg(feature = "github")]
     PrEvent,
+    /// Item of the pull request issue timeline, e.g. review or commit
+    #[cfg(feature = "github")]
+    PrTimeline,
+    /// Pull request review with its approval state
+    #[cfg(feature = "github")]
+    PrReview,
+    /// Inline comment of a pull request review
+    #[cfg(feature = "github")]
+    PrReviewComment,
+    /// Published release of a repository
+    #[cfg(feature = "github")]
+    Release,
+    /// Commit pushed to a repository branch
+    #[cfg(feature = "github")]
+    Commit,
+    /// GitHub Actions workflow run
+    #[cfg(feature = "github")]
+    WorkflowRun,
+    /// Check run of a pull request head commit
+    #[cfg(feature = "github")]
+    CheckRun,
+    /// Commit status of a pull request head commit
+    #[cfg(feature = "github")]
+    CommitStatus,
+    /// Star added to a repository
+    #[cfg(feature = "github")]
+    Star,
+    /// Fork of a repository
+    #[cfg(feature = "github")]
+    Fork,
+    /// Tag that was not found in the previous run
+    #[cfg(feature = "github")]
+    Tag,
+    /// Branch that was not found in the previous run
+    #[cfg(feature = "github")]
+    Branch,
+    /// Deployment of a repository to an environment
+    #[cfg(feature = "github")]
+    Deployment,
+    /// Status of a deployment, e.g. `success` or `failure`
+    #[cfg(feature = "github")]
+    DeploymentStatus,
+    /// Dependabot, code scanning or secret scanning alert
+    #[cfg(feature = "github")]
+    SecurityAlert,
+    /// Notification of the token user
+    #[cfg(feature = "github")]
+    Notification,
+    /// Milestone of a repository
+    #[cfg(feature = "github")]
+    Milestone,
+    /// Item of a GitHub project (Projects v2)
+    #[cfg(feature = "github")]
+    ProjectItem,
+    /// Reaction added to a pull request, e.g. `+1` or `heart`
+    #[cfg(feature = "github")]
+    Reaction,
+    /// Issue of a repository, see
+    /// [`crate::vendor::github::events::GitHub::get_issue`]
+    #[cfg(feature = "github")]
+    Issue,
+    /// Change of a repository setting, e.g. `topics` or `archived`
+    #[cfg(feature = "github")]
+    RepoChange,
+    /// Audit log entry of an organization, e.g. `repo.destroy`
+    #[cfg(feature = "github")]
+    AuditLog,
+    /// Member added to or removed from an organization team
+    #[cfg(feature = "github")]
+    TeamMembership,
+    /// Version of a GitHub package, e.g. a new container tag
+    #[cfg(feature = "github")]
+    PackageVersion,
 }
 
 /// Describe the event details that return from the vendors.
@@ -25,21 +120,665 @@ pub struct Event {
     pub date: Option<DateTime<Utc>>,
     pub priority: usize,
     pub row_data: Value, // pub status: String,
+    /// Metadata computed by enrichers and consumers, kept apart from the
+    /// vendor `row_data`, see [`crate::pipeline::annotate`]
+    pub annotations: HashMap<String, Value>,
+}
+
+impl Event {
+    /// Set an annotation, replacing the previous value of the key
+    ///
+    /// # Arguments
+    /// * `key` - Annotation name
+    /// * `value` - Annotation value
+    pub fn annotate(&mut self, key: &str, value: Value) {
+        self.annotations.insert(key.to_string(), value);
+    }
+
+    /// Return the annotation value of the key
+    #[must_use]
+    pub fn annotation(&self, key: &str) -> Option<&Value> {
+        self.annotations.get(key)
+    }
 }
 
-/// Operation type on the JSON value
-#[derive(Debug, Deserialize, Clone)]
+/// Operation type on the JSON value. parsed from the symbols shown by
+/// [`fmt::Display`] or from their aliases, see [`FromStr`]
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
 pub enum Operation {
-    #[serde(rename = "=")]
+    #[default]
     Equal,
-    #[serde(rename = "~")]
     Contains,
+    /// Match when the query value is an explicit JSON `null`
+    IsNull,
+    /// Match when the query value exists and is not JSON `null`
+    IsNotNull,
+    /// Match when the query value is a datetime equal to one of the values,
+    /// within the filter [`Tolerance`]
+    DateEqual,
+    /// Match when the query value is similar to one of the values, see
+    /// [`Filter::threshold`]
+    Fuzzy,
+    /// Match when the query value is a datetime within the last duration
+    /// given in the values, e.g. `7d` or `12h`
+    Within,
+    /// Match when the query value is a datetime older than the duration given
+    /// in the values, e.g. `7d` or `12h`
+    OlderThan,
+    /// Match when the query value is equal to the value of
+    /// [`Filter::other_query`] on the same document, e.g. to detect
+    /// self-merges
+    FieldEqual,
+    /// Match when the query value is equal to one of the values of another
+    /// document, see [`Filter::set`] and [`crate::jfilter::resolve_sets`]
+    InSet,
+    /// Match when the query value is not equal to any of the values, the
+    /// negation of [`Operation::Equal`]
+    NotEqual,
+    /// Match when the query value is greater than or equal to one of the
+    /// values. numbers are compared as numbers, datetimes as datetimes
+    GreaterOrEqual,
+    /// Match when the query value matches one of the regular expressions in
+    /// the values, e.g. `^release/v\d+`
+    Regexp,
+}
+
+impl FromStr for Operation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let operation = match s.trim().to_lowercase().as_str() {
+            "=" | "==" | "eq" | "equal" | "equals" | "in" => Self::Equal,
+            "~" | "contains" => Self::Contains,
+            "is_null" | "null" => Self::IsNull,
+            "is_not_null" | "not_null" => Self::IsNotNull,
+            "date=" | "date_equal" => Self::DateEqual,
+            "fuzzy" => Self::Fuzzy,
+            "within" => Self::Within,
+            "older_than" => Self::OlderThan,
+            "field=" | "field_equal" => Self::FieldEqual,
+            "in_set" => Self::InSet,
+            "!=" | "not_equal" | "not_in" => Self::NotEqual,
+            ">=" | "gte" | "greater_or_equal" => Self::GreaterOrEqual,
+            "=~" | "regexp" | "regex" => Self::Regexp,
+            "!~" | "not_contains" => {
+                bail!(
+                    "operation `{}` is not supported, use `~` with `negate: true`",
+                    s
+                )
+            }
+            _ => bail!("unknown operation `{}`", s),
+        };
+        Ok(operation)
+    }
+}
+
+impl TryFrom<String> for Operation {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Operation> for String {
+    fn from(operation: Operation) -> Self {
+        operation.to_string()
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Equal => "=",
+            Self::Contains => "~",
+            Self::IsNull => "is_null",
+            Self::IsNotNull => "is_not_null",
+            Self::DateEqual => "date=",
+            Self::Fuzzy => "fuzzy",
+            Self::Within => "within",
+            Self::OlderThan => "older_than",
+            Self::FieldEqual => "field=",
+            Self::InSet => "in_set",
+            Self::NotEqual => "!=",
+            Self::GreaterOrEqual => ">=",
+            Self::Regexp => "regexp",
+        };
+        f.write_str(symbol)
+    }
+}
+
+/// Unicode normalization form applied before comparing strings
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalization {
+    /// Canonical composition, e.g. `e` + combining acute accent become `é`
+    Nfc,
+    /// Compatibility composition, e.g. full-width `Ａ` become `A`
+    Nfkc,
+}
+
+/// How many elements of an array query value should match
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Quantifier {
+    /// At least one element match
+    #[default]
+    Any,
+    /// Every element match
+    All,
+    /// No element match
+    None,
+    /// At least the given number of elements match, e.g. `at_least: 2`
+    #[serde(rename = "at_least")]
+    AtLeast(usize),
+    /// At most the given number of elements match, e.g. `at_most: 1`
+    #[serde(rename = "at_most")]
+    AtMost(usize),
+}
+
+/// Transform of the query value, applied before the operation runs. String
+/// transforms are applied on each element of arrays and skip other values
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    Lowercase,
+    Trim,
+    /// Remove the prefix when present, e.g. `strip_prefix: refs/heads/`
+    StripPrefix(String),
+    /// Decode `%XX` escapes
+    UrlDecode,
+    /// Decode base64 to a UTF-8 string
+    Base64Decode,
+    /// Parse a JSON document embedded in a string
+    JsonParse,
+    /// Run a query on the value, e.g. on the result of
+    /// [`Transform::JsonParse`]
+    Query(String),
+}
+
+/// Allowed difference between two datetimes to be considered equal
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Tolerance {
+    /// Both datetimes fall on the same UTC calendar day
+    SameDay,
+    /// Both datetimes are at most the given duration apart
+    Within(Duration),
+}
+
+impl TryFrom<String> for Tolerance {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value == "day" {
+            return Ok(Self::SameDay);
+        }
+        Ok(Self::Within(utils::parse_duration(&value)?))
+    }
+}
+
+impl From<Tolerance> for String {
+    fn from(tolerance: Tolerance) -> Self {
+        match tolerance {
+            Tolerance::SameDay => "day".to_string(),
+            Tolerance::Within(duration) => utils::format_duration(duration),
+        }
+    }
 }
 
 /// Filter options
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Filter {
+    /// Optional filter name, carried through errors, traces and
+    /// [`MatchReport`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Optional human readable description of the filter intent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     pub query: String,
+    /// Not required by operations that do not compare with static values,
+    /// e.g. [`Operation::IsNull`] and [`Operation::FieldEqual`]
+    #[serde(default)]
     pub values: Vec<String>,
     pub operation: Operation,
+    /// Used by [`Operation::DateEqual`]. `day` or a duration like `5m`. when
+    /// not set the datetimes must be exactly equal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<Tolerance>,
+    /// Used by [`Operation::Fuzzy`]. Minimum Jaro-Winkler similarity between
+    /// 0.0 and 1.0, default to [`DEFAULT_FUZZY_THRESHOLD`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+    /// Invert the result of the operation
+    #[serde(default)]
+    pub negate: bool,
+    /// Used when the query value is an array
+    #[serde(default)]
+    pub quantifier: Quantifier,
+    /// jql query applied to each element when the query value is an array,
+    /// e.g. `"name"` to match an array of label objects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_query: Option<String>,
+    /// Compare numbers and booleans as strings, so `"5"` match `5` and
+    /// `"true"` match `true`
+    #[serde(default)]
+    pub coerce: bool,
+    /// Unicode normalization of both sides before [`Operation::Equal`] and
+    /// [`Operation::Contains`] comparisons
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<Normalization>,
+    /// Case-fold both sides before [`Operation::Equal`] and
+    /// [`Operation::Contains`] comparisons
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// Used by [`Operation::FieldEqual`]. jql query of the value to compare
+    /// with, e.g. `"user"."login"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_query: Option<String>,
+    /// [`Transform`]s applied in order on the query value before the
+    /// operation runs
+    #[serde(default)]
+    pub transforms: Vec<Transform>,
+    /// Used by [`Operation::InSet`]. Values of the filter taken from another
+    /// document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set: Option<SetRef>,
+}
+
+/// Values of another document, e.g. the logins of the team members, see
+/// [`Operation::InSet`]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SetRef {
+    /// Document name, a key of the documents given to
+    /// [`crate::jfilter::resolve_sets`]
+    pub document: String,
+    /// Query of the values in the document, e.g. `members[].login`
+    pub query: String,
+}
+
+impl Filter {
+    /// Filter name for errors and reports, the query when not named
+    #[must_use]
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.query)
+    }
+
+    /// Start building a filter on the query, e.g.
+    /// `Filter::query("user.login").equals(["kaplanelad"])`
+    ///
+    /// # Arguments
+    /// * `query` - Query of the value to match
+    #[must_use]
+    pub fn query(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Match when the query value is equal to one of the values, see
+    /// [`Operation::Equal`]
+    #[must_use]
+    pub fn equals<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with_operation(Operation::Equal, values)
+    }
+
+    /// Match when the query value contains one of the values, see
+    /// [`Operation::Contains`]
+    #[must_use]
+    pub fn contains<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with_operation(Operation::Contains, values)
+    }
+
+    /// Match when the query value is an explicit JSON `null`
+    #[must_use]
+    pub fn is_null(self) -> Self {
+        self.with_operation(Operation::IsNull, Vec::<String>::new())
+    }
+
+    /// Match when the query value exists and is not JSON `null`
+    #[must_use]
+    pub fn is_not_null(self) -> Self {
+        self.with_operation(Operation::IsNotNull, Vec::<String>::new())
+    }
+
+    /// Set the filter name, see [`Filter::name`]
+    #[must_use]
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Invert the result of the operation, see [`Filter::negate`]
+    #[must_use]
+    pub fn negated(mut self) -> Self {
+        self.negate = true;
+        self
+    }
+
+    /// Case-fold both sides before comparing, see [`Filter::ignore_case`]
+    #[must_use]
+    pub fn ignoring_case(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+
+    fn with_operation<I, S>(mut self, operation: Operation, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.operation = operation;
+        self.values = values.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Build a list of [`Filter`]s, `==` for [`Operation::Equal`] and `~` for
+/// [`Operation::Contains`]
+///
+/// ```
+/// let filters = webql::filters![
+///     "user.login" == ["kaplanelad"],
+///     "body" ~ ["example"],
+/// ];
+/// assert_eq!(filters.len(), 2);
+/// ```
+#[macro_export]
+macro_rules! filters {
+    ($($query:literal $op:tt [$($value:expr),* $(,)?]),* $(,)?) => {
+        vec![$($crate::filters!(@filter $query $op [$($value),*])),*]
+    };
+    (@filter $query:literal == [$($value:expr),*]) => {
+        $crate::data::Filter::query($query).equals([$($value),*])
+    };
+    (@filter $query:literal ~ [$($value:expr),*]) => {
+        $crate::data::Filter::query($query).contains([$($value),*])
+    };
+}
+
+/// [`Filter`] with a weight, used to rank items by how strongly they match
+/// instead of hard-filtering them
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WeightedFilter {
+    #[serde(flatten)]
+    pub filter: Filter,
+    /// Added to the score when the filter match. negative weights penalize
+    /// matching items. default to 1.0
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// Guards against pathological documents, e.g. from untrusted endpoints
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum nesting depth of arrays and objects
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// Maximum approximate document size in bytes, the sum of the strings,
+    /// keys and scalar values sizes
+    #[serde(default = "default_max_size")]
+    pub max_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+}
+
+fn default_max_depth() -> usize {
+    DEFAULT_MAX_DEPTH
+}
+
+fn default_max_size() -> usize {
+    DEFAULT_MAX_SIZE
+}
+
+/// Document exceeded one of the [`Limits`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    Depth { max: usize },
+    Size { max: usize },
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Depth { max } => write!(f, "document depth exceeds the limit of {}", max),
+            Self::Size { max } => write!(f, "document size exceeds the limit of {} bytes", max),
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+/// What to do with an item that failed the filters evaluation, e.g. when a
+/// query is not found in the item
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPolicy {
+    /// Return the error
+    #[default]
+    Fail,
+    /// Drop the item
+    Skip,
+    /// Keep the item as matched
+    Keep,
+}
+
+/// Sort direction of [`crate::jfilter::top_n`]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    /// Greatest first, e.g. most recent `updated_at` first
+    #[default]
+    Desc,
+}
+
+/// Result of a single [`Filter`]
+#[derive(Debug, Clone)]
+pub struct FilterResult {
+    /// Filter position in the filters list
+    pub index: usize,
+    /// [`Filter::name`]
+    pub name: Option<String>,
+    pub query: String,
+    pub is_match: bool,
+    /// Value extracted by the filter query
+    pub value: Value,
+}
+
+/// Result of all the filters on a JSON value
+#[derive(Debug, Clone, Default)]
+pub struct MatchReport {
+    pub results: Vec<FilterResult>,
+}
+
+impl MatchReport {
+    /// Return true when all the filters passed
+    #[must_use]
+    pub fn is_match(&self) -> bool {
+        self.results.iter().all(|result| result.is_match)
+    }
+
+    /// Filters that passed
+    pub fn passed(&self) -> impl Iterator<Item = &FilterResult> {
+        self.results.iter().filter(|result| result.is_match)
+    }
+
+    /// Filters that failed
+    pub fn failed(&self) -> impl Iterator<Item = &FilterResult> {
+        self.results.iter().filter(|result| !result.is_match)
+    }
+}
+
+/// Evaluation counters of a single filter query
+#[derive(Debug, Clone, Default)]
+pub struct FilterStat {
+    pub evaluations: u64,
+    pub matches: u64,
+    /// Evaluations that returned an error, e.g. the query was not found
+    pub failures: u64,
+    /// Total evaluation time
+    pub elapsed: time::Duration,
+}
+
+/// Query of a field found in a sample document, see
+/// [`crate::jfilter::inspect`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuerySuggestion {
+    /// Builtin query, e.g. `labels[].name`
+    pub query: String,
+    /// jql query, e.g. `"labels"|={"name"}."name"`. not set for fields
+    /// nested under more than one array level
+    pub jql: Option<String>,
+    /// JSON type of the field value, e.g. `string`
+    pub kind: &'static str,
+}
+
+/// Evaluation counters of the filtering engine, used to find filters that
+/// are expensive or never match
+#[derive(Debug, Clone, Default)]
+pub struct FilterStats {
+    pub evaluations: u64,
+    pub matches: u64,
+    pub failures: u64,
+    /// Counters by filter query
+    pub filters: BTreeMap<String, FilterStat>,
+}
+
+impl FilterStats {
+    /// Record a single filter evaluation
+    ///
+    /// # Arguments
+    /// * `query` - Filter query
+    /// * `is_match` - Evaluation result, `None` when the evaluation failed
+    /// * `elapsed` - Evaluation time
+    pub fn record(&mut self, query: &str, is_match: Option<bool>, elapsed: time::Duration) {
+        let stat = self.filters.entry(query.to_string()).or_default();
+        self.evaluations += 1;
+        stat.evaluations += 1;
+        stat.elapsed += elapsed;
+        match is_match {
+            Some(true) => {
+                self.matches += 1;
+                stat.matches += 1;
+            }
+            Some(false) => {}
+            None => {
+                self.failures += 1;
+                stat.failures += 1;
+            }
+        }
+    }
+
+    /// Queries of the filters that were evaluated but never matched
+    pub fn never_matched(&self) -> impl Iterator<Item = &str> {
+        self.filters
+            .iter()
+            .filter(|(_, stat)| stat.matches == 0)
+            .map(|(query, _)| query.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test_data {
+
+    use insta::assert_debug_snapshot;
+
+    use chrono::Duration;
+    use serde_json::json;
+
+    use super::{Filter, Operation, Quantifier, Tolerance, Transform, WeightedFilter};
+
+    #[test]
+    fn can_parse_operations() {
+        assert_debug_snapshot!(["==", "in", "~", "date=", "field="]
+            .iter()
+            .map(|s| s.parse::<Operation>().map(|op| op.to_string()))
+            .map(|op| op.map_err(|e| e.to_string()))
+            .collect::<Vec<_>>());
+        assert_debug_snapshot!(["!=", "not_in", ">=", "gte", "regexp", "=~", "!~", ">"]
+            .iter()
+            .map(|s| s.parse::<Operation>().map(|op| op.to_string()))
+            .map(|op| op.map_err(|e| e.to_string()))
+            .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_round_trip_operations() {
+        for operation in [
+            Operation::Equal,
+            Operation::Contains,
+            Operation::IsNull,
+            Operation::IsNotNull,
+            Operation::DateEqual,
+            Operation::Fuzzy,
+            Operation::Within,
+            Operation::OlderThan,
+            Operation::FieldEqual,
+            Operation::InSet,
+            Operation::NotEqual,
+            Operation::GreaterOrEqual,
+            Operation::Regexp,
+        ] {
+            assert_eq!(
+                operation.to_string().parse::<Operation>().unwrap(),
+                operation
+            );
+        }
+    }
+
+    #[test]
+    fn can_serialize_filters() {
+        let filters = vec![
+            WeightedFilter {
+                filter: Filter {
+                    tolerance: Some(Tolerance::Within(Duration::minutes(90))),
+                    operation: Operation::DateEqual,
+                    values: vec!["2022-10-20T10:00:00Z".to_string()],
+                    ..Filter::query("merged_at").named("merged on time")
+                },
+                weight: 2.0,
+            },
+            WeightedFilter {
+                filter: Filter {
+                    quantifier: Quantifier::AtLeast(2),
+                    transforms: vec![Transform::StripPrefix("team-".to_string())],
+                    ..Filter::query("labels[].name").equals(["rust", "infra"])
+                },
+                weight: 1.0,
+            },
+        ];
+
+        let value = serde_json::to_value(&filters).unwrap();
+        assert_debug_snapshot!(value);
+
+        let parsed: Vec<WeightedFilter> = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+        assert_eq!(
+            serde_json::to_value(Operation::FieldEqual).unwrap(),
+            json!("field=")
+        );
+    }
 }
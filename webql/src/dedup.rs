@@ -0,0 +1,514 @@
+//! Drop the events that were already emitted
+//!
+//! Polling the same window twice, or a pull request that is updated between
+//! polls, returns the same comments and events again. [`Dedup`] remembers the
+//! recently seen events, keyed by their [`crate::data::EventKind`] and id, in a bounded
+//! LRU. A [`SeenStore`] can back the LRU so the seen events survive between
+//! polling processes. The keys are vendor neutral, so a single [`Dedup`] can
+//! be shared by the events of all the vendors.
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use anyhow::{Context, Result};
+#[cfg(feature = "sqlite")]
+use rusqlite::{params, Connection, OptionalExtension};
+use tracing::debug;
+
+use crate::data::Event;
+
+/// Default number of keys kept by the [`Dedup`] LRU
+pub const DEFAULT_CAPACITY: usize = 10_000;
+/// Default number of keys kept by a [`JsonSeenStore`]
+pub const DEFAULT_STORE_CAPACITY: usize = 100_000;
+
+/// Persistent store of the seen event keys
+pub trait SeenStore: Send + Sync {
+    /// Return true when the key was seen
+    ///
+    /// # Errors
+    /// - When the store could not be read
+    fn contains(&self, key: &str) -> Result<bool>;
+
+    /// Remember the key
+    ///
+    /// # Errors
+    /// - When the store could not be written
+    fn insert(&self, key: &str) -> Result<()>;
+
+    /// Persist the keys inserted since the last flush. called once per
+    /// [`Dedup::retain_unseen`] batch
+    ///
+    /// # Errors
+    /// - When the store could not be written
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Seen event keys in memory, bounded by a capacity. the oldest keys are
+/// evicted first. A store created with [`JsonSeenStore::from_file`] writes the
+/// keys to a JSON file on [`SeenStore::flush`]
+pub struct JsonSeenStore {
+    seen: Mutex<SeenKeys>,
+    capacity: usize,
+    path: Option<PathBuf>,
+}
+
+/// Seen keys in insertion order
+#[derive(Debug, Default)]
+struct SeenKeys {
+    keys: HashSet<String>,
+    /// The first key is the oldest
+    order: VecDeque<String>,
+    /// Keys changed since the last flush
+    dirty: bool,
+}
+
+impl fmt::Debug for JsonSeenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonSeenStore")
+            .field("keys", &self.seen().keys.len())
+            .field("capacity", &self.capacity)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl Default for JsonSeenStore {
+    fn default() -> Self {
+        Self {
+            seen: Mutex::default(),
+            capacity: DEFAULT_STORE_CAPACITY,
+            path: None,
+        }
+    }
+}
+
+impl JsonSeenStore {
+    /// Create a store that is persisted to the given file. the file keys are
+    /// loaded when the file exists
+    ///
+    /// # Arguments
+    /// * `path` - Seen keys file path
+    ///
+    /// # Errors
+    /// - When the file exists and could not be read or parsed
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let order: VecDeque<String> = if path.exists() {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("could not read seen events {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("could not parse seen events {}", path.display()))?
+        } else {
+            VecDeque::new()
+        };
+
+        let store = Self {
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        };
+        {
+            let mut seen = store.seen();
+            for key in order {
+                seen.insert(key, store.capacity);
+            }
+            seen.dirty = false;
+        }
+        Ok(store)
+    }
+
+    /// Set the maximum number of kept keys, the oldest keys are evicted
+    /// first. default to [`DEFAULT_STORE_CAPACITY`]
+    #[must_use]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self.seen().evict(self.capacity);
+        self
+    }
+
+    fn seen(&self) -> MutexGuard<'_, SeenKeys> {
+        self.seen.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl SeenKeys {
+    /// Add the key, evicting the oldest keys above the capacity
+    fn insert(&mut self, key: String, capacity: usize) {
+        if self.keys.insert(key.clone()) {
+            self.order.push_back(key);
+            self.dirty = true;
+        }
+        self.evict(capacity);
+    }
+
+    fn evict(&mut self, capacity: usize) {
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+                self.dirty = true;
+            }
+        }
+    }
+}
+
+impl SeenStore for JsonSeenStore {
+    fn contains(&self, key: &str) -> Result<bool> {
+        Ok(self.seen().keys.contains(key))
+    }
+
+    fn insert(&self, key: &str) -> Result<()> {
+        self.seen().insert(key.to_string(), self.capacity);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut seen = self.seen();
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !seen.dirty {
+            return Ok(());
+        }
+        let content = serde_json::to_string(&seen.order)?;
+        fs::write(path, content)
+            .with_context(|| format!("could not write seen events {}", path.display()))?;
+        seen.dirty = false;
+        Ok(())
+    }
+}
+
+/// Seen event keys in a SQLite database. require `sqlite` feature flag on
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteSeenStore {
+    connection: Mutex<Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSeenStore {
+    /// Open the database, the seen events table is created when missing
+    ///
+    /// # Arguments
+    /// * `path` - Database file path
+    ///
+    /// # Errors
+    /// - When the database could not be opened
+    /// - When the seen events table could not be created
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .with_context(|| format!("could not open seen events database {}", path.display()))?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS webql_seen_events (key TEXT PRIMARY KEY)",
+            [],
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl SeenStore for SqliteSeenStore {
+    fn contains(&self, key: &str) -> Result<bool> {
+        let found: Option<i64> = self
+            .connection()
+            .query_row(
+                "SELECT 1 FROM webql_seen_events WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    fn insert(&self, key: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR IGNORE INTO webql_seen_events (key) VALUES (?1)",
+            params![key],
+        )?;
+        Ok(())
+    }
+}
+
+/// Recently seen keys, evicted by least recent use
+#[derive(Debug, Default)]
+struct Lru {
+    /// Key to its last use tick
+    ticks: HashMap<String, u64>,
+    /// Last use tick to key, the first entry is the least recently used
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Lru {
+    /// Mark the key as used, return true when it was already in the LRU
+    fn touch(&mut self, key: &str, capacity: usize) -> bool {
+        self.tick += 1;
+        let seen = match self.ticks.get_mut(key) {
+            Some(tick) => {
+                self.order.remove(tick);
+                *tick = self.tick;
+                true
+            }
+            None => {
+                self.ticks.insert(key.to_string(), self.tick);
+                false
+            }
+        };
+        self.order.insert(self.tick, key.to_string());
+
+        while self.order.len() > capacity {
+            let Some(oldest) = self.order.keys().next().copied() else {
+                break;
+            };
+            if let Some(evicted) = self.order.remove(&oldest) {
+                self.ticks.remove(&evicted);
+            }
+        }
+        seen
+    }
+}
+
+/// Drop the events that were already seen. opt-in, the vendors never drop
+/// events on their own
+pub struct Dedup {
+    capacity: usize,
+    recent: Mutex<Lru>,
+    store: Option<Arc<dyn SeenStore>>,
+}
+
+impl fmt::Debug for Dedup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dedup")
+            .field("capacity", &self.capacity)
+            .field("recent", &self.recent().ticks.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl Dedup {
+    /// Create an in memory dedup layer
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of keys kept in memory, the least
+    ///   recently seen keys are evicted first
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            recent: Mutex::default(),
+            store: None,
+        }
+    }
+
+    /// Back the LRU with a persistent store. keys evicted from the LRU, or
+    /// seen by a previous process, are looked up in the store
+    #[must_use]
+    pub fn with_store(mut self, store: Arc<dyn SeenStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Return the dedup key of the event
+    #[must_use]
+    pub fn key(event: &Event) -> String {
+        format!("{:?}:{}", event.kind, event.id)
+    }
+
+    /// Return the events that were not seen before, in their order, and
+    /// remember them. an event repeated in the given events is kept once
+    ///
+    /// # Arguments
+    /// * `events` - Events of a poll, of any vendor
+    ///
+    /// # Errors
+    /// - When the [`SeenStore`] could not be read or written
+    pub fn retain_unseen(&self, events: Vec<Event>) -> Result<Vec<Event>> {
+        let total = events.len();
+        let mut unseen = Vec::with_capacity(total);
+        for event in events {
+            let key = Self::key(&event);
+            if self.recent().touch(&key, self.capacity) {
+                continue;
+            }
+            if let Some(store) = &self.store {
+                if store.contains(&key)? {
+                    continue;
+                }
+                store.insert(&key)?;
+            }
+            unseen.push(event);
+        }
+        if let Some(store) = &self.store {
+            store.flush()?;
+        }
+        debug!(message = "drop seen events", seen = total - unseen.len());
+        Ok(unseen)
+    }
+
+    fn recent(&self) -> MutexGuard<'_, Lru> {
+        self.recent.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test_dedup {
+
+    use std::{collections::HashMap, env, fs, sync::Arc};
+
+    use serde_json::json;
+
+    use super::{Dedup, JsonSeenStore, SeenStore};
+    use crate::data::{Event, EventKind};
+
+    fn event(kind: EventKind, id: &str) -> Event {
+        Event {
+            kind,
+            id: id.to_string(),
+            parent_event_id: None,
+            name: id.to_string(),
+            link: None,
+            date: None,
+            priority: 1,
+            row_data: json!({}),
+            annotations: HashMap::new(),
+        }
+    }
+
+    fn ids(events: &[Event]) -> Vec<String> {
+        events.iter().map(Dedup::key).collect()
+    }
+
+    #[test]
+    fn can_drop_seen_events() {
+        let dedup = Dedup::default();
+        let first = dedup
+            .retain_unseen(vec![
+                event(EventKind::Edited, "1"),
+                event(EventKind::Alert, "1"),
+                event(EventKind::Edited, "1"),
+            ])
+            .unwrap();
+        assert_eq!(ids(&first), vec!["Edited:1", "Alert:1"]);
+
+        let second = dedup
+            .retain_unseen(vec![
+                event(EventKind::Edited, "1"),
+                event(EventKind::Edited, "2"),
+            ])
+            .unwrap();
+        assert_eq!(ids(&second), vec!["Edited:2"]);
+    }
+
+    #[test]
+    fn can_evict_least_recently_seen() {
+        let dedup = Dedup::new(2);
+        dedup
+            .retain_unseen(vec![
+                event(EventKind::Edited, "1"),
+                event(EventKind::Edited, "2"),
+            ])
+            .unwrap();
+        // touch 1, so 2 is evicted by 3
+        dedup
+            .retain_unseen(vec![
+                event(EventKind::Edited, "1"),
+                event(EventKind::Edited, "3"),
+            ])
+            .unwrap();
+
+        let events = dedup
+            .retain_unseen(vec![
+                event(EventKind::Edited, "1"),
+                event(EventKind::Edited, "2"),
+            ])
+            .unwrap();
+        assert_eq!(ids(&events), vec!["Edited:2"]);
+    }
+
+    #[test]
+    fn can_persist_seen_events() {
+        let path = env::temp_dir().join(format!("webql-{}-seen.json", std::process::id()));
+        let store = Arc::new(JsonSeenStore::from_file(&path).unwrap());
+        let dedup = Dedup::default().with_store(store);
+        dedup
+            .retain_unseen(vec![event(EventKind::Edited, "1")])
+            .unwrap();
+
+        let restored = Arc::new(JsonSeenStore::from_file(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(restored.contains("Edited:1").unwrap());
+
+        let events = Dedup::default()
+            .with_store(restored)
+            .retain_unseen(vec![
+                event(EventKind::Edited, "1"),
+                event(EventKind::Alert, "1"),
+            ])
+            .unwrap();
+        assert_eq!(ids(&events), vec!["Alert:1"]);
+    }
+
+    #[test]
+    fn can_bound_seen_events_and_flush_once_per_batch() {
+        let path = env::temp_dir().join(format!("webql-{}-bounded.json", std::process::id()));
+        let store = Arc::new(JsonSeenStore::from_file(&path).unwrap().with_capacity(2));
+        store.insert("Edited:1").unwrap();
+        assert!(!path.exists());
+
+        let dedup = Dedup::default().with_store(store.clone());
+        dedup
+            .retain_unseen(vec![
+                event(EventKind::Edited, "2"),
+                event(EventKind::Edited, "3"),
+            ])
+            .unwrap();
+        assert!(!store.contains("Edited:1").unwrap());
+
+        let restored = JsonSeenStore::from_file(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(content, r#"["Edited:2","Edited:3"]"#);
+        assert!(restored.contains("Edited:3").unwrap());
+        assert!(!restored.contains("Edited:1").unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn can_persist_to_sqlite() {
+        use super::SqliteSeenStore;
+
+        let path = env::temp_dir().join(format!("webql-{}-seen.db", std::process::id()));
+        let store = SqliteSeenStore::open(&path).unwrap();
+        store.insert("Edited:1").unwrap();
+        store.insert("Edited:1").unwrap();
+        drop(store);
+
+        let restored = SqliteSeenStore::open(&path).unwrap();
+        let seen = (
+            restored.contains("Edited:1").unwrap(),
+            restored.contains("Edited:2").unwrap(),
+        );
+        drop(restored);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(seen, (true, false));
+    }
+}
@@ -0,0 +1,140 @@
+//! Detect edits to PR descriptions and comments
+//!
+//! The [`BodyStore`] remembers the body of every seen event. When an event
+//! body changes between runs, an [`EventKind::Edited`] event is emitted with
+//! a unified diff of the body text. The store is serializable so callers can
+//! persist it between runs.
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+use similar::TextDiff;
+use tracing::debug;
+
+use crate::data::{Event, EventKind};
+
+/// Remember the last seen body of events, keyed by the event kind and id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BodyStore {
+    bodies: HashMap<String, String>,
+    /// Number of edits of each event, used in the edit ids
+    #[serde(default)]
+    edits: HashMap<String, u64>,
+}
+
+impl BodyStore {
+    /// Number of remembered bodies
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    /// Return true when no body is remembered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+}
+
+/// Compare the events body with the [`BodyStore`] and return an
+/// [`EventKind::Edited`] event for every changed body. The edit id is the
+/// event kind and id with the edit number of the event, so every edit of
+/// the same event has its own id, even when a body is edited back and forth.
+/// The edited event kind is kept in the row data under `kind`. The store is
+/// updated with the new bodies.
+///
+/// # Arguments
+/// * `store` - Bodies from the previous runs
+/// * `events` - Current events. only events with `body` in the row data are
+///   checked
+pub fn detect_edits(store: &mut BodyStore, events: &[Event]) -> Vec<Event> {
+    let mut edits = vec![];
+    for event in events {
+        let Some(body) = event.row_data.get("body").and_then(|b| b.as_str()) else {
+            continue;
+        };
+        let key = format!("{:?}:{}", event.kind, event.id);
+
+        if let Some(previous) = store.bodies.get(&key) {
+            if previous != body {
+                debug!(message = "event body was edited", key);
+                let edit = store.edits.entry(key.clone()).or_default();
+                *edit += 1;
+                let diff = TextDiff::from_lines(previous.as_str(), body)
+                    .unified_diff()
+                    .header("previous", "current")
+                    .to_string();
+                edits.push(Event {
+                    kind: EventKind::Edited,
+                    id: format!("{}:{}", key, edit),
+                    parent_event_id: Some(event.id.clone()),
+                    name: "edited".to_string(),
+                    link: event.link.clone(),
+                    date: event.date,
+                    priority: event.priority,
+                    row_data: json!({
+                        "kind": format!("{:?}", event.kind),
+                        "diff": diff,
+                        "previous_body": previous,
+                        "body": body,
+                    }),
+                    annotations: HashMap::new(),
+                });
+            }
+        }
+
+        store.bodies.insert(key, body.to_string());
+    }
+    edits
+}
+
+#[cfg(all(test, feature = "github"))]
+mod test_edits {
+
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::{detect_edits, BodyStore};
+    use crate::data::{Event, EventKind};
+
+    fn comment(body: &str) -> Event {
+        Event {
+            kind: EventKind::PrComment,
+            id: "1".to_string(),
+            parent_event_id: Some("10".to_string()),
+            name: body.to_string(),
+            link: None,
+            date: None,
+            priority: 1,
+            row_data: json!({ "body": body }),
+            annotations: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn can_detect_edits() {
+        let mut store = BodyStore::default();
+
+        assert!(detect_edits(&mut store, &[comment("require tests\n")]).is_empty());
+        assert!(detect_edits(&mut store, &[comment("require tests\n")]).is_empty());
+
+        let edits = detect_edits(&mut store, &[comment("require tests and docs\n")]);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0].row_data["diff"],
+            "--- previous\n+++ current\n@@ -1 +1 @@\n-require tests\n+require tests and docs\n"
+        );
+        assert_eq!(edits[0].row_data["kind"], "PrComment");
+        assert_eq!(edits[0].id, "PrComment:1:1");
+        assert_eq!(store.len(), 1);
+
+        // editing the body back and forth keeps a distinct id per edit
+        let ids = ["require tests\n", "require tests and docs\n"]
+            .iter()
+            .flat_map(|body| detect_edits(&mut store, &[comment(body)]))
+            .map(|edit| edit.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["PrComment:1:2", "PrComment:1:3"]);
+    }
+}
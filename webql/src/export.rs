@@ -0,0 +1,137 @@
+//! Export events to other formats
+//!
+//! # iCalendar
+//! [`to_icalendar`] produce an `.ics` feed of the events that have a due
+//! date, so matched work items show up in calendars.
+use chrono::{DateTime, Utc};
+
+use crate::data::Event;
+
+/// iCalendar product identifier
+const ICALENDAR_PRODID: &str = "-//rusty-ferris-club//webql//EN";
+/// Maximum iCalendar line length in octets
+const ICALENDAR_LINE_LENGTH: usize = 75;
+
+/// Export events with a due date to iCalendar format. Events without a due
+/// date are skipped.
+///
+/// # Arguments
+/// * `events` - List of [`Event`]
+/// * `due_date` - Return the due date of the event, e.g.
+///   [`milestone_due_date`]
+pub fn to_icalendar<F>(events: &[Event], due_date: F) -> String
+where
+    F: Fn(&Event) -> Option<DateTime<Utc>>,
+{
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", ICALENDAR_PRODID),
+    ];
+
+    for event in events {
+        let Some(due) = due_date(event) else {
+            continue;
+        };
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{:?}-{}@webql", event.kind, event.id));
+        lines.push(format!(
+            "DTSTAMP:{}",
+            format_date_time(event.date.unwrap_or(due))
+        ));
+        lines.push(format!("DTSTART:{}", format_date_time(due)));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.name)));
+        if let Some(link) = &event.link {
+            lines.push(format!("URL:{}", link));
+        }
+        // iCalendar priority is between 1 (highest) and 9 (lowest)
+        lines.push(format!("PRIORITY:{}", event.priority.clamp(1, 9)));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| format!("{}\r\n", fold_line(line)))
+        .collect()
+}
+
+/// Due date of a GitHub milestone, taken from `milestone.due_on` in the event
+/// row data
+pub fn milestone_due_date(event: &Event) -> Option<DateTime<Utc>> {
+    event
+        .row_data
+        .get("milestone")?
+        .get("due_on")?
+        .as_str()?
+        .parse()
+        .ok()
+}
+
+/// Format [`DateTime<Utc>`] as iCalendar UTC date time
+fn format_date_time(date: DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape iCalendar text value
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold lines longer than [`ICALENDAR_LINE_LENGTH`] octets
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > ICALENDAR_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(all(test, feature = "github"))]
+mod test_export {
+
+    use std::collections::HashMap;
+
+    use insta::assert_debug_snapshot;
+    use serde_json::json;
+
+    use super::{fold_line, milestone_due_date, to_icalendar};
+    use crate::data::{Event, EventKind};
+
+    #[test]
+    fn can_export_icalendar() {
+        let event = |id: &str, row_data| Event {
+            kind: EventKind::PR,
+            id: id.to_string(),
+            parent_event_id: None,
+            name: "Release v1.0, final".to_string(),
+            link: Some("https://github.com/rusty-ferris-club/webql/pull/1".to_string()),
+            date: "2022-10-20T10:00:00Z".parse().ok(),
+            priority: 1,
+            row_data,
+            annotations: HashMap::new(),
+        };
+        let events = vec![
+            event(
+                "1",
+                json!({"milestone": {"due_on": "2022-11-01T00:00:00Z"}}),
+            ),
+            event("2", json!({"milestone": null})),
+        ];
+        assert_debug_snapshot!(to_icalendar(&events, milestone_due_date));
+    }
+
+    #[test]
+    fn can_fold_line() {
+        assert_debug_snapshot!(fold_line(&"a".repeat(80)));
+    }
+}
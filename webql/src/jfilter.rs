@@ -6,58 +6,858 @@
 #![doc = include_str!("../examples/json-filter.rs")]
 //! ```
 //!
-use anyhow::{bail, Result};
-use serde_json::Value;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    io::{BufRead, Write},
+    time::Instant,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use regex::RegexBuilder;
+use serde_json::{Map, Value};
 use tracing::debug;
+use unicode_normalization::UnicodeNormalization;
+
+use super::{
+    data::{
+        ErrorPolicy, Filter, FilterResult, FilterStats, LimitError, Limits, MatchReport,
+        Normalization, Operation, Quantifier, QuerySuggestion, SortOrder, Tolerance, Transform,
+        WeightedFilter, DEFAULT_FUZZY_THRESHOLD,
+    },
+    utils,
+};
 
-use super::data::{Filter, Operation};
+pub mod fs;
+pub mod query;
 
-/// Filter json [`Value`] object with the [`Filter`] settings
+/// Filter json [`Value`] object with the [`Filter`] settings. The document
+/// is checked against the default [`Limits`]
 ///
 /// # Arguments
 /// * `data` - Event data
 /// * `filters` - List of filter queries
 ///
 /// # Errors
+/// - When the document exceeds the default [`Limits`], as [`LimitError`]
 /// - When [`Filter`] query is invalid
 pub fn is_match_filters(data: &Value, filters: &[Filter]) -> Result<bool> {
-    for filter in filters {
-        let query_result = match jql::walker(data, &filter.query) {
-            Ok(q) => q,
-            Err(e) => {
-                debug!(message = "could not run jql walker", query = filter.query);
-                bail!("{}", e)
-            }
-        };
-
-        // allow single_match_else for now to support more type cases.
-        #[allow(clippy::single_match_else)]
-        let is_match = match &query_result {
-            // check query value type for different logic
-            Value::Array(v) => is_match_array(v, filter),
-            // Default meaning is string value
-            _ => {
-                let event_value = query_result.as_str().unwrap_or("");
-                if event_value.is_empty() {
-                    debug!(message = "value is empty", query = filter.query);
-                    bail!("query {} result is empty", filter.query);
+    is_match_filters_with_limits(data, filters, &Limits::default())
+}
+
+/// Same as [`is_match_filters`] with custom [`Limits`]
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `filters` - List of filter queries
+/// * `limits` - Document [`Limits`]
+///
+/// # Errors
+/// - When the document exceeds the limits, as [`LimitError`]
+/// - When [`Filter`] query is invalid
+pub fn is_match_filters_with_limits(
+    data: &Value,
+    filters: &[Filter],
+    limits: &Limits,
+) -> Result<bool> {
+    check_limits(data, limits)?;
+    evaluate_filters(data, filters, None)
+}
+
+/// Same as [`is_match_filters`], and record each filter evaluation in the
+/// given [`FilterStats`]
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `filters` - List of filter queries
+/// * `stats` - [`FilterStats`] to update
+///
+/// # Errors
+/// - When the document exceeds the default [`Limits`], as [`LimitError`]
+/// - When [`Filter`] query is invalid
+pub fn is_match_filters_with_stats(
+    data: &Value,
+    filters: &[Filter],
+    stats: &mut FilterStats,
+) -> Result<bool> {
+    check_limits(data, &Limits::default())?;
+    evaluate_filters(data, filters, Some(stats))
+}
+
+/// Run all the filters on the json [`Value`] and return a [`MatchReport`]
+/// with the result and the extracted value of each filter
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `filters` - List of filter queries
+///
+/// # Errors
+/// - When [`Filter`] query is invalid
+pub fn match_report(data: &Value, filters: &[Filter]) -> Result<MatchReport> {
+    let mut cache = QueryCache::new(data);
+    Ok(MatchReport {
+        results: filters
+            .iter()
+            .enumerate()
+            .map(|(index, filter)| evaluate_filter(&mut cache, index, filter))
+            .collect::<Result<Vec<_>>>()?,
+    })
+}
+
+/// Return the relevance score of the json [`Value`], the sum of the weights
+/// of the matching filters. A filter that could not be evaluated, e.g. when
+/// the query is not found, does not match
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `filters` - List of [`WeightedFilter`]
+pub fn score(data: &Value, filters: &[WeightedFilter]) -> f64 {
+    let mut cache = QueryCache::new(data);
+    filters
+        .iter()
+        .enumerate()
+        .filter(
+            |(index, weighted)| match evaluate_filter(&mut cache, *index, &weighted.filter) {
+                Ok(result) => result.is_match,
+                Err(e) => {
+                    debug!(
+                        message = "could not evaluate weighted filter",
+                        query = weighted.filter.query,
+                        err = e.to_string(),
+                    );
+                    false
                 }
-                debug!(
-                    message = "found value from pull request data",
-                    value = event_value,
-                    query = filter.query,
-                );
-                is_match_string(event_value, filter)
+            },
+        )
+        .map(|(_, weighted)| weighted.weight)
+        .sum()
+}
+
+/// Return only the items that match all the filters. an item that failed
+/// the filters evaluation returns an error, see [`filter_array_with_policy`]
+///
+/// # Arguments
+/// * `items` - List of json documents
+/// * `filters` - List of filter queries
+///
+/// # Errors
+/// - When [`Filter`] query is invalid for one of the items
+pub fn filter_array(items: &[Value], filters: &[Filter]) -> Result<Vec<Value>> {
+    filter_array_with_policy(items, filters, ErrorPolicy::Fail)
+}
+
+/// Same as [`filter_array`], the items are evaluated on multiple cores.
+/// The matched items keep the input order
+///
+/// # Arguments
+/// * `items` - List of json documents
+/// * `filters` - List of filter queries
+///
+/// # Errors
+/// - When [`Filter`] query is invalid for one of the items
+#[cfg(feature = "rayon")]
+pub fn par_filter_array(items: &[Value], filters: &[Filter]) -> Result<Vec<Value>> {
+    items
+        .par_iter()
+        .filter_map(|item| match is_match_filters(item, filters) {
+            Ok(true) => Some(Ok(item.clone())),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect()
+}
+
+/// Return only the items that match all the filters
+///
+/// # Arguments
+/// * `items` - List of json documents
+/// * `filters` - List of filter queries
+/// * `policy` - [`ErrorPolicy`] for items that failed the filters evaluation
+///
+/// # Errors
+/// - When [`Filter`] query is invalid for one of the items and the policy is
+///   [`ErrorPolicy::Fail`]
+pub fn filter_array_with_policy(
+    items: &[Value],
+    filters: &[Filter],
+    policy: ErrorPolicy,
+) -> Result<Vec<Value>> {
+    let mut matched = vec![];
+    for item in items {
+        let is_match = match is_match_filters(item, filters) {
+            Ok(is_match) => is_match,
+            Err(e) => match policy {
+                ErrorPolicy::Fail => return Err(e),
+                ErrorPolicy::Skip => false,
+                ErrorPolicy::Keep => true,
+            },
+        };
+        if is_match {
+            matched.push(item.clone());
+        }
+    }
+    Ok(matched)
+}
+
+/// Read newline-delimited JSON, run the filters on each line and write the
+/// matching lines to the sink. Lines are streamed, so the input is never
+/// fully loaded into memory. an invalid line returns an error, see
+/// [`filter_ndjson_with_policy`]
+///
+/// # Arguments
+/// * `reader` - NDJSON input
+/// * `filters` - List of filter queries
+/// * `sink` - Output of the matching lines
+///
+/// # Errors
+/// - When could not read from the reader or write to the sink
+/// - When a line is not a valid JSON or a [`Filter`] query is invalid for it
+pub fn filter_ndjson(reader: impl BufRead, filters: &[Filter], sink: impl Write) -> Result<usize> {
+    filter_ndjson_with_policy(reader, filters, sink, ErrorPolicy::Fail)
+}
+
+/// Same as [`filter_ndjson`] with an [`ErrorPolicy`] for invalid lines.
+/// Returns the number of matching lines
+///
+/// # Arguments
+/// * `reader` - NDJSON input
+/// * `filters` - List of filter queries
+/// * `sink` - Output of the matching lines
+/// * `policy` - [`ErrorPolicy`] for lines that are not a valid JSON or failed
+///   the filters evaluation
+///
+/// # Errors
+/// - When could not read from the reader or write to the sink
+/// - When a line is invalid and the policy is [`ErrorPolicy::Fail`]
+pub fn filter_ndjson_with_policy(
+    reader: impl BufRead,
+    filters: &[Filter],
+    mut sink: impl Write,
+    policy: ErrorPolicy,
+) -> Result<usize> {
+    let mut matched = 0;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let is_match = serde_json::from_str::<Value>(&line)
+            .map_err(anyhow::Error::from)
+            .and_then(|item| is_match_filters(&item, filters))
+            .with_context(|| format!("invalid ndjson line {}", index + 1));
+        let is_match = match is_match {
+            Ok(is_match) => is_match,
+            Err(e) => match policy {
+                ErrorPolicy::Fail => return Err(e),
+                ErrorPolicy::Skip => false,
+                ErrorPolicy::Keep => true,
+            },
+        };
+        if is_match {
+            writeln!(sink, "{}", line)?;
+            matched += 1;
+        }
+    }
+    sink.flush()?;
+    Ok(matched)
+}
+
+/// Resolve the `${NAME}` and `${env:NAME}` placeholders of the filters
+/// queries and values, so a shared config can be evaluated per user
+///
+/// # Arguments
+/// * `filters` - List of filter queries
+/// * `variables` - User provided variables. `${NAME}` placeholders that are
+///   not found here are read from the environment variables
+///
+/// # Errors
+/// - When a placeholder could not be resolved
+pub fn resolve_filters(
+    filters: &[Filter],
+    variables: &HashMap<String, String>,
+) -> Result<Vec<Filter>> {
+    filters
+        .iter()
+        .map(|filter| {
+            Ok(Filter {
+                query: utils::substitute_variables(&filter.query, variables)?,
+                values: filter
+                    .values
+                    .iter()
+                    .map(|value| utils::substitute_variables(value, variables))
+                    .collect::<Result<Vec<_>>>()?,
+                item_query: filter
+                    .item_query
+                    .as_ref()
+                    .map(|query| utils::substitute_variables(query, variables))
+                    .transpose()?,
+                ..filter.clone()
+            })
+        })
+        .collect()
+}
+
+/// Run the jql query on the json [`Value`] and return the query result. The
+/// document is checked against the default [`Limits`]
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `query` - jql query
+///
+/// # Errors
+/// - When the document exceeds the default [`Limits`], as [`LimitError`]
+/// - When the query is invalid or not found in the data
+pub fn extract(data: &Value, query: &str) -> Result<Value> {
+    extract_with_limits(data, query, &Limits::default())
+}
+
+/// Same as [`extract`] with custom [`Limits`]
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `query` - jql query
+/// * `limits` - Document [`Limits`]
+///
+/// # Errors
+/// - When the document exceeds the limits, as [`LimitError`]
+/// - When the query is invalid or not found in the data
+pub fn extract_with_limits(data: &Value, query: &str, limits: &Limits) -> Result<Value> {
+    check_limits(data, limits)?;
+    walk(data, query)
+}
+
+/// Check the document depth and size
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `limits` - Document [`Limits`]
+///
+/// # Errors
+/// - When the document exceeds the limits, as [`LimitError`]
+pub fn check_limits(data: &Value, limits: &Limits) -> Result<()> {
+    let mut size: usize = 0;
+    let mut stack = vec![(data, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        if depth > limits.max_depth {
+            return Err(LimitError::Depth {
+                max: limits.max_depth,
             }
+            .into());
+        }
+        size += match value {
+            Value::String(s) => s.len(),
+            Value::Array(items) => {
+                stack.extend(items.iter().map(|item| (item, depth + 1)));
+                0
+            }
+            Value::Object(map) => {
+                stack.extend(map.values().map(|item| (item, depth + 1)));
+                map.keys().map(String::len).sum()
+            }
+            Value::Number(_) => 8,
+            Value::Bool(_) | Value::Null => 1,
         };
+        if size > limits.max_size {
+            return Err(LimitError::Size {
+                max: limits.max_size,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Run the query without checking the document [`Limits`]. jql queries,
+/// e.g. `"user"."login"`, run on the jql engine, other queries, e.g.
+/// `user.login`, run on the builtin [`query`] evaluator
+fn walk(data: &Value, query: &str) -> Result<Value> {
+    if !query::is_jql(query) {
+        return query::evaluate(data, query);
+    }
+    walk_jql(data, query)
+}
+
+#[cfg(feature = "jql")]
+fn walk_jql(data: &Value, query: &str) -> Result<Value> {
+    match jql::walker(data, query) {
+        Ok(q) => Ok(q),
+        Err(e) => {
+            debug!(message = "could not run jql walker", query);
+            bail!("{}", e)
+        }
+    }
+}
+
+#[cfg(not(feature = "jql"))]
+fn walk_jql(_data: &Value, query: &str) -> Result<Value> {
+    bail!(
+        "query {} uses jql syntax which requires the jql feature, use a builtin query like `user.login` instead",
+        query
+    )
+}
+
+/// Resolve the values of the [`Operation::InSet`] filters from the
+/// [`Filter::set`] documents, e.g. keep pull requests whose `user.login` is
+/// in a team members document. Numbers and booleans of the set are compared
+/// as strings
+///
+/// # Arguments
+/// * `filters` - List of filter queries
+/// * `documents` - Documents of the sets by name
+///
+/// # Errors
+/// - When an [`Operation::InSet`] filter has no [`Filter::set`]
+/// - When a set document is not found or its query fails
+pub fn resolve_sets(filters: &[Filter], documents: &HashMap<String, Value>) -> Result<Vec<Filter>> {
+    filters
+        .iter()
+        .map(|filter| {
+            if filter.operation != Operation::InSet {
+                return Ok(filter.clone());
+            }
+            let Some(set) = &filter.set else {
+                bail!("filter {} is in_set without a set", filter.label());
+            };
+            let Some(document) = documents.get(&set.document) else {
+                bail!("set document {} not found", set.document);
+            };
+            let values = match walk(document, &set.query)? {
+                Value::Array(items) => items,
+                value => vec![value],
+            };
+            Ok(Filter {
+                values: values
+                    .iter()
+                    .filter_map(|value| match value {
+                        Value::String(s) => Some(s.clone()),
+                        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+                        _ => None,
+                    })
+                    .collect(),
+                ..filter.clone()
+            })
+        })
+        .collect()
+}
+
+/// Project the json [`Value`] to an object with only the requested fields.
+/// the object keys are the queries
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `queries` - List of jql queries
+///
+/// # Errors
+/// - When the document exceeds the default [`Limits`], as [`LimitError`]
+/// - When one of the queries is invalid or not found in the data
+pub fn select(data: &Value, queries: &[String]) -> Result<Value> {
+    check_limits(data, &Limits::default())?;
+    let mut selected = Map::new();
+    for query in queries {
+        selected.insert(query.clone(), walk(data, query)?);
+    }
+    Ok(Value::Object(selected))
+}
+
+/// Remove the documents whose key was already seen in a previous document,
+/// e.g. overlapping pages of a paginated API. The first document of each key
+/// is kept and the order is preserved
+///
+/// # Arguments
+/// * `items` - List of documents
+/// * `query` - Query of the document key, e.g. `id`
+///
+/// # Errors
+/// - When the query is invalid or not found in one of the documents, the
+///   error context has the document index
+pub fn dedup_by(items: &[Value], query: &str) -> Result<Vec<Value>> {
+    let mut seen = HashSet::new();
+    let mut unique = vec![];
+    for (index, item) in items.iter().enumerate() {
+        let key = walk(item, query).with_context(|| format!("item {} has no key", index))?;
+        if seen.insert(key.to_string()) {
+            unique.push(item.clone());
+        }
+    }
+    Ok(unique)
+}
+
+/// Return the first `limit` documents sorted by the query value, e.g. the 10
+/// most recent pull requests by `updated_at`. Numbers are compared by value
+/// and strings lexicographically, so RFC 3339 datetimes sort by time.
+/// Documents without the query value come last in both orders, ties keep
+/// the input order
+///
+/// # Arguments
+/// * `items` - List of documents, e.g. the result of [`filter_array`]
+/// * `query` - Query of the sort value
+/// * `order` - [`SortOrder`]
+/// * `limit` - Maximum number of documents to return
+#[must_use]
+pub fn top_n(items: &[Value], query: &str, order: SortOrder, limit: usize) -> Vec<Value> {
+    let mut keyed = items
+        .iter()
+        .map(|item| (walk(item, query).ok().filter(|key| !key.is_null()), item))
+        .collect::<Vec<_>>();
+    keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => match order {
+            SortOrder::Asc => compare_values(a, b),
+            SortOrder::Desc => compare_values(b, a),
+        },
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+    keyed
+        .into_iter()
+        .take(limit)
+        .map(|(_, item)| item.clone())
+        .collect()
+}
+
+/// Compare two query values of the same type, values of different types are
+/// ordered by type
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// List the queries of the fields of a sample document with their JSON
+/// type, to help writing filters. Array elements are merged, so a field
+/// found in any element is listed once. When the sample is an array, each
+/// element is inspected as a document
+///
+/// # Arguments
+/// * `sample` - Sample document
+#[must_use]
+pub fn inspect(sample: &Value) -> Vec<QuerySuggestion> {
+    let mut suggestions = vec![];
+    match sample {
+        Value::Array(items) => {
+            for item in items {
+                inspect_value(item, "", &JqlPath::Path(String::new()), &mut suggestions);
+            }
+        }
+        _ => inspect_value(sample, "", &JqlPath::Path(String::new()), &mut suggestions),
+    }
+    suggestions
+}
 
-        if !is_match {
+/// jql path of an inspected value, see [`inspect`]
+enum JqlPath {
+    /// Path of object fields, e.g. `"user"."login"`
+    Path(String),
+    /// Elements of the array at the given path
+    Items(String),
+    /// Field of array elements, e.g. `"labels"|={"name"}."name"`
+    Plucked(String),
+    /// No jql query is suggested
+    Unsupported,
+}
+
+impl JqlPath {
+    fn field(&self, key: &str) -> Self {
+        match self {
+            Self::Path(path) if path.is_empty() => Self::Path(format!("\"{}\"", key)),
+            Self::Path(path) => Self::Path(format!("{}.\"{}\"", path, key)),
+            Self::Items(path) => Self::Plucked(format!("{}|={{\"{}\"}}.\"{}\"", path, key, key)),
+            Self::Plucked(_) | Self::Unsupported => Self::Unsupported,
+        }
+    }
+
+    fn items(&self) -> Self {
+        match self {
+            Self::Path(path) if !path.is_empty() => Self::Items(path.clone()),
+            _ => Self::Unsupported,
+        }
+    }
+
+    fn query(&self) -> Option<String> {
+        match self {
+            Self::Path(path) | Self::Plucked(path) => Some(path.clone()),
+            Self::Items(_) | Self::Unsupported => None,
+        }
+    }
+}
+
+/// Add the suggestions of the object fields of the value, see [`inspect`]
+///
+/// # Arguments
+/// * `value` - Inspected value
+/// * `path` - Builtin query of the value
+/// * `jql` - [`JqlPath`] of the value
+/// * `suggestions` - Found suggestions
+fn inspect_value(value: &Value, path: &str, jql: &JqlPath, suggestions: &mut Vec<QuerySuggestion>) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    for (key, field) in map {
+        let query = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        let field_jql = jql.field(key);
+        if !suggestions.iter().any(|s| s.query == query) {
+            suggestions.push(QuerySuggestion {
+                query: query.clone(),
+                jql: field_jql.query(),
+                kind: query::type_name(field),
+            });
+        }
+        match field {
+            Value::Array(items) => {
+                let items_path = format!("{}[]", query);
+                for item in items {
+                    inspect_value(item, &items_path, &field_jql.items(), suggestions);
+                }
+            }
+            _ => inspect_value(field, &query, &field_jql, suggestions),
+        }
+    }
+}
+
+/// jql query results of a single document, so filters with the same query
+/// run the jql walker once per document
+struct QueryCache<'a> {
+    data: &'a Value,
+    results: HashMap<String, Value>,
+}
+
+impl<'a> QueryCache<'a> {
+    fn new(data: &'a Value) -> Self {
+        Self {
+            data,
+            results: HashMap::new(),
+        }
+    }
+
+    /// Return the query result, running the jql walker only on the first
+    /// call. failures are not cached
+    ///
+    /// # Errors
+    /// - When the query is invalid or not found in the data
+    fn query(&mut self, query: &str) -> Result<Value> {
+        if let Some(value) = self.results.get(query) {
+            return Ok(value.clone());
+        }
+        let value = walk(self.data, query)?;
+        self.results.insert(query.to_string(), value.clone());
+        Ok(value)
+    }
+}
+
+/// Run the filters until the first filter that does not match
+///
+/// # Errors
+/// - When [`Filter`] query is invalid
+fn evaluate_filters(
+    data: &Value,
+    filters: &[Filter],
+    mut stats: Option<&mut FilterStats>,
+) -> Result<bool> {
+    let mut cache = QueryCache::new(data);
+    for (index, filter) in filters.iter().enumerate() {
+        let started = Instant::now();
+        let result = evaluate_filter(&mut cache, index, filter);
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.record(
+                &filter.query,
+                result.as_ref().ok().map(|r| r.is_match),
+                started.elapsed(),
+            );
+        }
+        if !result?.is_match {
             return Ok(false);
         }
     }
     Ok(true)
 }
 
+/// Run a single filter on the json [`Value`]
+///
+/// # Arguments
+/// * `cache` - [`QueryCache`] of the event data
+/// * `index` - Filter position in the filters list
+/// * `filter` - Filter query
+///
+/// # Errors
+/// - When [`Filter`] query is invalid, the error context has the filter
+///   index and [`Filter::label`]
+fn evaluate_filter(cache: &mut QueryCache, index: usize, filter: &Filter) -> Result<FilterResult> {
+    evaluate_filter_value(cache, index, filter)
+        .with_context(|| format!("filter {} ({}) failed", index, filter.label()))
+}
+
+/// Run a single filter on the json [`Value`], see [`evaluate_filter`]
+///
+/// # Errors
+/// - When [`Filter`] query is invalid
+fn evaluate_filter_value(
+    cache: &mut QueryCache,
+    index: usize,
+    filter: &Filter,
+) -> Result<FilterResult> {
+    let query_result = apply_transforms(cache.query(&filter.query)?, &filter.transforms)?;
+
+    // allow single_match_else for now to support more type cases.
+    #[allow(clippy::single_match_else)]
+    let is_match = match (&filter.operation, &query_result) {
+        // null checks run on the raw value, a missing field fails in the jql walker
+        (Operation::IsNull, v) => v.is_null(),
+        (Operation::IsNotNull, v) => !v.is_null(),
+        (Operation::FieldEqual, v) => is_match_field(cache, v, filter)?,
+        // check query value type for different logic
+        (_, Value::Array(v)) => is_match_array(v, filter),
+        // Default meaning is string value
+        _ => {
+            let event_value = value_as_str(&query_result, filter);
+            let event_value = event_value.as_ref();
+            if event_value.is_empty() {
+                debug!(
+                    message = "value is empty",
+                    filter = filter.label(),
+                    query = filter.query
+                );
+                bail!("query {} result is empty", filter.query);
+            }
+            debug!(
+                message = "found value from pull request data",
+                value = event_value,
+                filter = filter.label(),
+                query = filter.query,
+            );
+            is_match_string(event_value, filter)
+        }
+    };
+
+    Ok(FilterResult {
+        index,
+        name: filter.name.clone(),
+        query: filter.query.clone(),
+        // negate inverts the operation result
+        is_match: is_match != filter.negate,
+        value: query_result,
+    })
+}
+
+/// Apply the [`Transform`]s in order on the query value
+///
+/// # Errors
+/// - When a value could not be decoded or parsed
+/// - When a [`Transform::Query`] is invalid
+fn apply_transforms(value: Value, transforms: &[Transform]) -> Result<Value> {
+    transforms.iter().try_fold(value, |value, transform| {
+        apply_transform(value, transform)
+            .with_context(|| format!("transform {:?} failed", transform))
+    })
+}
+
+/// Apply a single [`Transform`], see [`apply_transforms`]
+///
+/// # Errors
+/// - When a value could not be decoded or parsed
+fn apply_transform(value: Value, transform: &Transform) -> Result<Value> {
+    if let Transform::Query(query) = transform {
+        return walk(&value, query);
+    }
+
+    let text = match value {
+        Value::String(text) => text,
+        Value::Array(items) => {
+            return Ok(Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| apply_transform(item, transform))
+                    .collect::<Result<_>>()?,
+            ))
+        }
+        other => return Ok(other),
+    };
+    Ok(match transform {
+        Transform::Lowercase => Value::String(text.to_lowercase()),
+        Transform::Trim => Value::String(text.trim().to_string()),
+        Transform::StripPrefix(prefix) => Value::String(
+            text.strip_prefix(prefix.as_str())
+                .map_or_else(|| text.clone(), str::to_string),
+        ),
+        Transform::UrlDecode => Value::String(utils::url_decode(&text)?),
+        Transform::Base64Decode => Value::String(
+            String::from_utf8(utils::base64_decode(&text)?)
+                .context("base64 decoded value is not UTF-8")?,
+        ),
+        Transform::JsonParse => serde_json::from_str(&text)?,
+        Transform::Query(_) => unreachable!("query transform is applied above"),
+    })
+}
+
+/// Convert the query value to string for matching. Numbers and booleans are
+/// converted only when [`Filter::coerce`] is set, other values are empty.
+/// [`Operation::GreaterOrEqual`] always converts numbers
+///
+/// # Arguments
+/// * `value` - Query value
+/// * `filter` - Group filters
+fn value_as_str<'a>(value: &'a Value, filter: &Filter) -> Cow<'a, str> {
+    match value {
+        Value::String(s) => Cow::Borrowed(s),
+        Value::Number(n) if filter.coerce || filter.operation == Operation::GreaterOrEqual => {
+            Cow::Owned(n.to_string())
+        }
+        Value::Bool(b) if filter.coerce => Cow::Owned(b.to_string()),
+        _ => Cow::Borrowed(""),
+    }
+}
+
+/// Apply the filter [`Normalization`] and case folding on the text
+///
+/// # Arguments
+/// * `text` - Text to normalize
+/// * `filter` - Group filters
+fn normalize_text<'a>(text: &'a str, filter: &Filter) -> Cow<'a, str> {
+    let text = match filter.normalize {
+        Some(Normalization::Nfc) => Cow::Owned(text.nfc().collect::<String>()),
+        Some(Normalization::Nfkc) => Cow::Owned(text.nfkc().collect::<String>()),
+        None => Cow::Borrowed(text),
+    };
+    if filter.ignore_case {
+        Cow::Owned(text.to_lowercase())
+    } else {
+        text
+    }
+}
+
+/// Check if both strings are the same number, e.g. `5` and `5.0`
+fn is_equal_number(a: &str, b: &str) -> bool {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => (a - b).abs() < f64::EPSILON,
+        _ => false,
+    }
+}
+
 /// Chec
 ///
 /// # Arguments
@@ -65,16 +865,13 @@ pub fn is_match_filters(data: &Value, filters: &[Filter]) -> Result<bool> {
 /// * `filter` - Group filters
 fn is_match_string(val_str: &str, filter: &Filter) -> bool {
     match filter.operation {
-        Operation::Equal => {
-            debug!(
-                message = "check equal value",
-                group_values = format!("{:?}", filter.values),
-                value = val_str,
-                operation = "equal",
-            );
-            filter.values.contains(&val_str.to_string())
-        }
+        // sets are resolved to values, see resolve_sets
+        Operation::Equal | Operation::InSet => is_match_equal(val_str, filter),
+        Operation::NotEqual => !is_match_equal(val_str, filter),
+        Operation::GreaterOrEqual => is_match_greater_or_equal(val_str, filter),
+        Operation::Regexp => is_match_regexp(val_str, filter),
         Operation::Contains => {
+            let val = normalize_text(val_str, filter);
             let mut exit = false;
             for group_val in &filter.values {
                 debug!(
@@ -83,89 +880,695 @@ fn is_match_string(val_str: &str, filter: &Filter) -> bool {
                     value = val_str,
                     operation = "contains",
                 );
-                if val_str.contains(group_val) {
+                if val.contains(normalize_text(group_val, filter).as_ref()) {
                     exit = true;
                     break;
                 }
             }
             exit
         }
+        Operation::DateEqual => is_match_date(val_str, filter),
+        Operation::Fuzzy => {
+            let threshold = filter.threshold.unwrap_or(DEFAULT_FUZZY_THRESHOLD);
+            filter.values.iter().any(|group_val| {
+                let similarity = strsim::jaro_winkler(val_str, group_val);
+                debug!(
+                    message = "check fuzzy values",
+                    group_value = group_val,
+                    value = val_str,
+                    similarity,
+                    threshold,
+                    operation = "fuzzy",
+                );
+                similarity >= threshold
+            })
+        }
+        Operation::Within | Operation::OlderThan => is_match_relative_date(val_str, filter),
+        Operation::IsNull | Operation::IsNotNull | Operation::FieldEqual => false,
+    }
+}
+
+/// Check if the value is equal to one of the filter values
+///
+/// # Arguments
+/// * `val_str` - Filter value
+/// * `filter` - Group filters
+fn is_match_equal(val_str: &str, filter: &Filter) -> bool {
+    debug!(
+        message = "check equal value",
+        group_values = format!("{:?}", filter.values),
+        value = val_str,
+        operation = "equal",
+    );
+    let val = normalize_text(val_str, filter);
+    filter.values.iter().any(|group_val| {
+        normalize_text(group_val, filter) == val
+            || (filter.coerce && is_equal_number(group_val, val_str))
+    })
+}
+
+/// Check if the value is greater than or equal to one of the filter values.
+/// both sides are compared as numbers, then as datetimes. other values never
+/// match
+///
+/// # Arguments
+/// * `val_str` - Filter value
+/// * `filter` - Group filters
+fn is_match_greater_or_equal(val_str: &str, filter: &Filter) -> bool {
+    filter.values.iter().any(|group_val| {
+        debug!(
+            message = "check greater or equal values",
+            group_value = group_val,
+            value = val_str,
+            operation = "greater or equal",
+        );
+        if let (Ok(val), Ok(group_val)) = (val_str.parse::<f64>(), group_val.parse::<f64>()) {
+            return val >= group_val;
+        }
+        match (
+            val_str.parse::<DateTime<Utc>>(),
+            group_val.parse::<DateTime<Utc>>(),
+        ) {
+            (Ok(date), Ok(group_date)) => date >= group_date,
+            _ => false,
+        }
+    })
+}
+
+/// Check if the value matches one of the regular expressions in the filter
+/// values. invalid expressions never match
+///
+/// # Arguments
+/// * `val_str` - Filter value
+/// * `filter` - Group filters
+fn is_match_regexp(val_str: &str, filter: &Filter) -> bool {
+    filter.values.iter().any(|group_val| {
+        debug!(
+            message = "check regexp values",
+            group_value = group_val,
+            value = val_str,
+            operation = "regexp",
+        );
+        match RegexBuilder::new(group_val)
+            .case_insensitive(filter.ignore_case)
+            .build()
+        {
+            Ok(regex) => regex.is_match(val_str),
+            Err(e) => {
+                debug!(
+                    message = "could not compile regexp",
+                    group_value = group_val,
+                    err = e.to_string(),
+                );
+                false
+            }
+        }
+    })
+}
+
+/// Check if the query value is equal to the [`Filter::other_query`] value on
+/// the same document. strings are compared after normalization, other
+/// values must be the same JSON value
+///
+/// # Arguments
+/// * `cache` - [`QueryCache`] of the event data
+/// * `value` - Query value
+/// * `filter` - Group filters
+///
+/// # Errors
+/// - When [`Filter::other_query`] is not set, invalid or not found
+fn is_match_field(cache: &mut QueryCache, value: &Value, filter: &Filter) -> Result<bool> {
+    let Some(other_query) = &filter.other_query else {
+        bail!("filter {} requires other_query", filter.label());
+    };
+    let other = cache.query(other_query)?;
+    debug!(
+        message = "check field values",
+        value = format!("{:?}", value),
+        other = format!("{:?}", other),
+        other_query,
+        operation = "field_equal",
+    );
+
+    Ok(match (value, &other) {
+        (Value::String(a), Value::String(b)) => {
+            normalize_text(a, filter) == normalize_text(b, filter)
+        }
+        _ => value == &other,
+    })
+}
+
+/// Check if the given datetime is within / older than one of the durations
+/// in the filter values, relative to now
+///
+/// # Arguments
+/// * `val_str` - Datetime value
+/// * `filter` - Group filters
+fn is_match_relative_date(val_str: &str, filter: &Filter) -> bool {
+    let date = match val_str.parse::<DateTime<Utc>>() {
+        Ok(d) => d,
+        Err(e) => {
+            debug!(
+                message = "could not convert value to date time",
+                value = val_str,
+                err = e.to_string(),
+            );
+            return false;
+        }
+    };
+
+    let now = Utc::now();
+    filter.values.iter().any(|group_val| {
+        let duration = match utils::parse_duration(group_val) {
+            Ok(d) => d,
+            Err(e) => {
+                debug!(
+                    message = "could not parse duration",
+                    group_value = group_val,
+                    err = e.to_string(),
+                );
+                return false;
+            }
+        };
+        match filter.operation {
+            Operation::OlderThan => date < now - duration,
+            _ => date >= now - duration,
+        }
+    })
+}
+
+/// Check if the given datetime is equal to one of the filter values within
+/// the filter [`Tolerance`]
+///
+/// # Arguments
+/// * `val_str` - Datetime value
+/// * `filter` - Group filters
+fn is_match_date(val_str: &str, filter: &Filter) -> bool {
+    let date = match val_str.parse::<DateTime<Utc>>() {
+        Ok(d) => d,
+        Err(e) => {
+            debug!(
+                message = "could not convert value to date time",
+                value = val_str,
+                err = e.to_string(),
+            );
+            return false;
+        }
+    };
+
+    filter
+        .values
+        .iter()
+        .filter_map(|group_val| group_val.parse::<DateTime<Utc>>().ok())
+        .any(|group_date| {
+            debug!(
+                message = "check date values",
+                group_value = group_date.to_rfc3339(),
+                value = val_str,
+                operation = "date equal",
+            );
+            match &filter.tolerance {
+                None => group_date == date,
+                Some(Tolerance::SameDay) => {
+                    group_date.naive_utc().date() == date.naive_utc().date()
+                }
+                Some(Tolerance::Within(duration)) => {
+                    let diff = if group_date > date {
+                        group_date - date
+                    } else {
+                        date - group_date
+                    };
+                    diff <= *duration
+                }
+            }
+        })
+}
+
+/// Run group filters on a array, the [`Quantifier`] of the filter decide how
+/// many elements should match. when [`Filter::item_query`] is set, the query
+/// runs on each element before matching
+///
+/// # Arguments
+/// * `values` - List of values
+/// * `filter` - Group filters
+fn is_match_array(values: &[Value], filter: &Filter) -> bool {
+    let mut matches = values.iter().map(|value| {
+        let item = match &filter.item_query {
+            Some(item_query) => match walk(value, item_query) {
+                Ok(v) => Cow::Owned(v),
+                Err(e) => {
+                    debug!(
+                        message = "could not run query on array item",
+                        query = item_query,
+                        err = e.to_string(),
+                    );
+                    return false;
+                }
+            },
+            None => Cow::Borrowed(value),
+        };
+        is_match_string(&value_as_str(&item, filter), filter)
+    });
+
+    match filter.quantifier {
+        Quantifier::Any => matches.any(|is_match| is_match),
+        Quantifier::All => matches.all(|is_match| is_match),
+        Quantifier::None => !matches.any(|is_match| is_match),
+        Quantifier::AtLeast(n) => matches.filter(|is_match| *is_match).take(n).count() == n,
+        Quantifier::AtMost(n) => matches.filter(|is_match| *is_match).count() <= n,
+    }
+}
+
+#[cfg(test)]
+mod test_jfilter {
+
+    use std::collections::HashMap;
+
+    use chrono::{Duration, Utc};
+    use insta::assert_debug_snapshot;
+    use serde_json::json;
+
+    use super::{
+        ErrorPolicy, Filter, FilterStats, LimitError, Limits, Normalization, Operation, Quantifier,
+        SortOrder, Tolerance, Transform, Value, WeightedFilter,
+    };
+    use crate::{
+        jfilter::{
+            dedup_by, filter_array, filter_array_with_policy, filter_ndjson,
+            filter_ndjson_with_policy, inspect, is_match_array, is_match_filters,
+            is_match_filters_with_limits, is_match_filters_with_stats, is_match_string,
+            match_report, resolve_filters, resolve_sets, score, select, top_n, QueryCache,
+        },
+        utils,
+    };
+
+    #[test]
+    fn is_equal_match_string() {
+        let filter = Filter {
+            query: "".to_string(),
+            values: vec!["foo".to_string(), "exists-value".to_string()],
+            operation: Operation::Equal,
+            ..Filter::default()
+        };
+        assert_debug_snapshot!(is_match_string("exists-value", &filter));
+        assert_debug_snapshot!(is_match_string("equal-value", &filter));
+    }
+
+    #[test]
+    fn is_contains_match_string() {
+        let filter = Filter {
+            query: "".to_string(),
+            values: vec!["foo".to_string(), "exists-value".to_string()],
+            operation: Operation::Contains,
+            ..Filter::default()
+        };
+        assert_debug_snapshot!(is_match_string("exists-value", &filter));
+        assert_debug_snapshot!(is_match_string("contains-value", &filter));
+    }
+
+    #[test]
+    fn is_contains_match_array() {
+        let filter = Filter {
+            query: "".to_string(),
+            values: vec!["foo".to_string(), "contains".to_string()],
+            operation: Operation::Contains,
+            ..Filter::default()
+        };
+        assert_debug_snapshot!(is_match_array(
+            &[
+                Value::String("val".to_string()),
+                Value::String("value-contains".to_string())
+            ],
+            &filter
+        ));
+        assert_debug_snapshot!(is_match_array(
+            &[
+                Value::String("val".to_string()),
+                Value::String("val-2".to_string())
+            ],
+            &filter
+        ));
+    }
+
+    #[test]
+    fn can_match_filters() {
+        let json = json!({
+            "body": "some example",
+            "labels": [
+                {
+                    "name": "label-1",
+                },
+                {
+                    "name": "label-2",
+                },
+            ],
+            "user" : {
+                "login": "kaplanelad"
+            }
+        });
+        let filter = vec![
+            Filter {
+                query: "body".to_string(),
+                values: vec!["foo".to_string(), "example".to_string()],
+                operation: Operation::Contains,
+                ..Filter::default()
+            },
+            Filter {
+                query: "user.login".to_string(),
+                values: vec!["foo".to_string(), "kaplanelad".to_string()],
+                operation: Operation::Equal,
+                ..Filter::default()
+            },
+            Filter {
+                query: "labels[].name".to_string(),
+                values: vec!["foo".to_string(), "label-1".to_string()],
+                operation: Operation::Equal,
+                ..Filter::default()
+            },
+            Filter {
+                query: "labels[].name".to_string(),
+                values: vec!["foo".to_string(), "label".to_string()],
+                operation: Operation::Contains,
+                ..Filter::default()
+            },
+        ];
+        assert_debug_snapshot!(is_match_filters(&json, &filter));
+    }
+
+    #[test]
+    fn can_filter_ndjson() {
+        let input = r#"{"id": 1, "label": "bug"}
+{"id": 2, "label": "docs"}
+
+{"id": 3, "label": "bug"}
+not json
+"#;
+        let filters = vec![Filter {
+            query: "label".to_string(),
+            values: vec!["bug".to_string()],
+            operation: Operation::Equal,
+            ..Filter::default()
+        }];
+
+        let mut output = vec![];
+        assert_debug_snapshot!(filter_ndjson_with_policy(
+            input.as_bytes(),
+            &filters,
+            &mut output,
+            ErrorPolicy::Skip
+        ));
+        assert_debug_snapshot!(String::from_utf8(output).unwrap());
+        assert_debug_snapshot!(
+            filter_ndjson(input.as_bytes(), &filters, std::io::sink()).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn can_limit_document_depth_and_size() {
+        let filters = vec![Filter {
+            query: "body".to_string(),
+            values: vec!["fix".to_string()],
+            operation: Operation::Contains,
+            ..Filter::default()
+        }];
+        let json = json!({"body": "fix", "nested": {"a": {"b": [1]}}});
+
+        assert!(is_match_filters(&json, &filters).unwrap());
+
+        let depth = is_match_filters_with_limits(
+            &json,
+            &filters,
+            &Limits {
+                max_depth: 2,
+                ..Limits::default()
+            },
+        )
+        .unwrap_err();
+        assert_debug_snapshot!(depth.downcast_ref::<LimitError>());
+
+        let size = is_match_filters_with_limits(
+            &json,
+            &filters,
+            &Limits {
+                max_size: 10,
+                ..Limits::default()
+            },
+        )
+        .unwrap_err();
+        assert_debug_snapshot!(size.to_string());
+
+        let mut deep = json!({"body": "fix"});
+        for _ in 0..100 {
+            deep = json!({"nested": deep});
+        }
+        let mut stats = FilterStats::default();
+        assert!(is_match_filters_with_stats(&deep, &filters, &mut stats)
+            .unwrap_err()
+            .downcast_ref::<LimitError>()
+            .is_some());
     }
-}
 
-/// Run group filters on a array
-///
-/// # Arguments
-/// * `values` - List of values
-/// * `filter` - Group filters
-fn is_match_array(values: &Vec<Value>, filter: &Filter) -> bool {
-    for value in values {
-        let pr_value = value.as_str().unwrap_or("");
-        if is_match_string(pr_value, filter) {
-            return true;
+    #[test]
+    fn can_collect_filter_stats() {
+        let filters = vec![
+            Filter {
+                query: "user.login".to_string(),
+                values: vec!["kaplanelad".to_string()],
+                operation: Operation::Equal,
+                ..Filter::default()
+            },
+            Filter {
+                query: "body".to_string(),
+                values: vec!["release".to_string()],
+                operation: Operation::Contains,
+                ..Filter::default()
+            },
+        ];
+        let mut stats = FilterStats::default();
+        for item in [
+            json!({"user": {"login": "kaplanelad"}, "body": "fix"}),
+            json!({"user": {"login": "dotan"}, "body": "fix"}),
+            json!({"body": "fix"}),
+        ] {
+            let _ = is_match_filters_with_stats(&item, &filters, &mut stats);
         }
+
+        assert_eq!(
+            (stats.evaluations, stats.matches, stats.failures),
+            (4, 1, 1)
+        );
+        assert_debug_snapshot!(stats
+            .filters
+            .iter()
+            .map(|(query, stat)| (
+                query.as_str(),
+                stat.evaluations,
+                stat.matches,
+                stat.failures
+            ))
+            .collect::<Vec<_>>());
+        assert_debug_snapshot!(stats.never_matched().collect::<Vec<_>>());
     }
-    false
-}
 
-#[cfg(test)]
-mod test_jfilter {
+    #[test]
+    fn can_score_weighted_filters() {
+        let json = json!({
+            "title": "fix: panic on empty body",
+            "labels": ["bug", "p1"],
+        });
+        let filters: Vec<WeightedFilter> = serde_json::from_value(json!([
+            {"query": "title", "values": ["fix"], "operation": "~", "weight": 2.0},
+            {"query": "labels", "values": ["p1"], "operation": "="},
+            {"query": "labels", "values": ["wip"], "operation": "=", "weight": -5.0},
+            {"query": "missing", "values": ["x"], "operation": "=", "weight": 10.0}
+        ]))
+        .unwrap();
 
-    use insta::assert_debug_snapshot;
-    use serde_json::json;
+        assert_debug_snapshot!(score(&json, &filters));
+        assert_debug_snapshot!(score(
+            &json!({"title": "docs", "labels": ["wip"]}),
+            &filters
+        ));
+    }
+
+    #[test]
+    fn can_resolve_filters_variables() {
+        let json = json!({
+            "user" : {
+                "login": "kaplanelad"
+            }
+        });
+        let filters = vec![Filter {
+            query: "user.login".to_string(),
+            values: vec!["${GITHUB_USER}".to_string()],
+            operation: Operation::Equal,
+            ..Filter::default()
+        }];
+        let variables = HashMap::from([("GITHUB_USER".to_string(), "kaplanelad".to_string())]);
 
-    use super::{Filter, Operation, Value};
-    use crate::jfilter::{is_match_array, is_match_filters, is_match_string};
+        let resolved = resolve_filters(&filters, &variables).unwrap();
+        assert_debug_snapshot!(resolved[0].values);
+        assert_debug_snapshot!(is_match_filters(&json, &resolved));
+        assert!(resolve_filters(&filters, &HashMap::new()).is_err());
+    }
 
     #[test]
-    fn is_equal_match_string() {
-        let filter = Filter {
+    fn can_match_null_filters() {
+        let json = json!({
+            "milestone": null,
+            "assignee": {
+                "login": "kaplanelad"
+            }
+        });
+        let filter = |query: &str, operation: Operation| {
+            vec![Filter {
+                query: query.to_string(),
+                values: vec![],
+                operation,
+                ..Filter::default()
+            }]
+        };
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter("milestone", Operation::IsNull)
+        ));
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter("assignee", Operation::IsNull)
+        ));
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter("assignee", Operation::IsNotNull)
+        ));
+        assert!(is_match_filters(&json, &filter("labels", Operation::IsNull)).is_err());
+    }
+
+    #[test]
+    fn is_date_equal_match_string() {
+        let filter = |tolerance: Option<Tolerance>| Filter {
             query: "".to_string(),
-            values: vec!["foo".to_string(), "exists-value".to_string()],
-            operation: Operation::Equal,
+            values: vec!["2022-10-20T10:00:00Z".to_string()],
+            operation: Operation::DateEqual,
+            tolerance,
+            ..Filter::default()
         };
-        assert_debug_snapshot!(is_match_string("exists-value", &filter));
-        assert_debug_snapshot!(is_match_string("equal-value", &filter));
+        assert_debug_snapshot!(is_match_string("2022-10-20T10:00:00Z", &filter(None)));
+        assert_debug_snapshot!(is_match_string("2022-10-20T10:00:05Z", &filter(None)));
+        assert_debug_snapshot!(is_match_string(
+            "2022-10-20T23:59:00Z",
+            &filter(Some(Tolerance::SameDay))
+        ));
+        assert_debug_snapshot!(is_match_string(
+            "2022-10-20T10:04:00Z",
+            &filter(Some(Tolerance::Within(
+                utils::parse_duration("5m").unwrap()
+            )))
+        ));
+        assert_debug_snapshot!(is_match_string(
+            "2022-10-20T09:54:00Z",
+            &filter(Some(Tolerance::Within(
+                utils::parse_duration("5m").unwrap()
+            )))
+        ));
     }
 
     #[test]
-    fn is_contains_match_string() {
+    fn is_fuzzy_match_string() {
         let filter = Filter {
             query: "".to_string(),
-            values: vec!["foo".to_string(), "exists-value".to_string()],
-            operation: Operation::Contains,
+            values: vec!["kubernetes".to_string()],
+            operation: Operation::Fuzzy,
+            ..Filter::default()
         };
-        assert_debug_snapshot!(is_match_string("exists-value", &filter));
-        assert_debug_snapshot!(is_match_string("contains-value", &filter));
+        assert_debug_snapshot!(is_match_string("kuberentes", &filter));
+        assert_debug_snapshot!(is_match_string("docker", &filter));
+        assert_debug_snapshot!(is_match_string(
+            "kuberentes",
+            &Filter {
+                threshold: Some(1.0),
+                ..filter.clone()
+            }
+        ));
     }
 
     #[test]
-    fn is_contains_match_array() {
-        let filter = Filter {
+    fn can_match_negate_filters() {
+        let json = json!({
+            "user" : {
+                "login": "kaplanelad"
+            }
+        });
+        let filter = |values: Vec<String>| {
+            vec![Filter {
+                query: "user.login".to_string(),
+                values,
+                operation: Operation::Equal,
+                negate: true,
+                ..Filter::default()
+            }]
+        };
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter(vec!["dependabot".to_string()])
+        ));
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter(vec!["kaplanelad".to_string()])
+        ));
+    }
+
+    #[test]
+    fn can_match_array_quantifiers() {
+        let labels = [
+            Value::String("team-a".to_string()),
+            Value::String("team-b".to_string()),
+        ];
+        let filter = |values: Vec<&str>, quantifier: Quantifier| Filter {
             query: "".to_string(),
-            values: vec!["foo".to_string(), "contains".to_string()],
-            operation: Operation::Contains,
+            values: values.iter().map(ToString::to_string).collect(),
+            operation: Operation::Equal,
+            quantifier,
+            ..Filter::default()
         };
         assert_debug_snapshot!(is_match_array(
-            &vec![
-                Value::String("val".to_string()),
-                Value::String("value-contains".to_string())
-            ],
-            &filter
+            &labels,
+            &filter(vec!["team-a", "team-b"], Quantifier::All)
         ));
         assert_debug_snapshot!(is_match_array(
-            &vec![
-                Value::String("val".to_string()),
-                Value::String("val-2".to_string())
-            ],
-            &filter
+            &labels,
+            &filter(vec!["team-a"], Quantifier::All)
+        ));
+        assert_debug_snapshot!(is_match_array(
+            &labels,
+            &filter(vec!["team-c"], Quantifier::None)
+        ));
+        assert_debug_snapshot!(is_match_array(
+            &labels,
+            &filter(vec!["team-b"], Quantifier::None)
+        ));
+
+        let quantifier: Quantifier = serde_json::from_value(json!({"at_least": 2})).unwrap();
+        assert!(is_match_array(
+            &labels,
+            &filter(vec!["team-a", "team-b"], quantifier)
+        ));
+        assert!(!is_match_array(
+            &labels,
+            &filter(vec!["team-a"], Quantifier::AtLeast(2))
+        ));
+        assert!(is_match_array(
+            &labels,
+            &filter(vec!["team-a"], Quantifier::AtMost(1))
+        ));
+        assert!(!is_match_array(
+            &labels,
+            &filter(vec!["team-a", "team-b"], Quantifier::AtMost(1))
         ));
     }
 
     #[test]
-    fn can_match_filters() {
+    fn can_match_array_of_objects() {
         let json = json!({
-            "body": "some example",
             "labels": [
                 {
                     "name": "label-1",
@@ -174,32 +1577,510 @@ mod test_jfilter {
                     "name": "label-2",
                 },
             ],
-            "user" : {
-                "login": "kaplanelad"
-            }
         });
-        let filter = vec![
+        let filter = |item_query: Option<String>| {
+            vec![Filter {
+                query: "labels".to_string(),
+                values: vec!["label-2".to_string()],
+                operation: Operation::Equal,
+                item_query,
+                ..Filter::default()
+            }]
+        };
+        assert_debug_snapshot!(is_match_filters(&json, &filter(None)));
+        assert_debug_snapshot!(is_match_filters(&json, &filter(Some("name".to_string()))));
+    }
+
+    #[test]
+    fn can_match_field_equal_filters() {
+        let merged_by = |login: &str| {
+            json!({
+                "user": {"login": "kaplanelad"},
+                "merged_by": {"login": login},
+            })
+        };
+        let filters = vec![Filter {
+            query: "merged_by.login".to_string(),
+            operation: Operation::FieldEqual,
+            other_query: Some("user.login".to_string()),
+            ..Filter::default()
+        }];
+
+        assert_debug_snapshot!(is_match_filters(&merged_by("kaplanelad"), &filters));
+        assert_debug_snapshot!(is_match_filters(&merged_by("dotan"), &filters));
+        assert!(is_match_filters(
+            &merged_by("kaplanelad"),
+            &[Filter {
+                other_query: None,
+                ..filters[0].clone()
+            }]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn can_build_filters() {
+        let json = json!({
+            "body": "some example",
+            "user": {"login": "kaplanelad"},
+            "merged_at": null,
+        });
+
+        let filters = [
+            Filter::query("user.login").equals(["kaplanelad"]),
+            Filter::query("body").contains(["foo", "example"]),
+            Filter::query("merged_at").is_null(),
+            Filter::query("user.login")
+                .equals(["KAPLANELAD"])
+                .ignoring_case(),
+            Filter::query("body").contains(["draft"]).negated(),
+        ];
+        assert!(is_match_filters(&json, &filters).unwrap());
+
+        let filters = crate::filters![
+            "user.login" == ["kaplanelad"],
+            "body" ~ ["foo", "bar"],
+        ];
+        assert!(!is_match_filters(&json, &filters).unwrap());
+        assert_debug_snapshot!(filters);
+    }
+
+    #[test]
+    fn can_match_builtin_queries() {
+        let json = json!({
+            "user": {"login": "kaplanelad"},
+            "labels": [{"name": "bug"}, {"name": "p1"}],
+        });
+        let filters = vec![
             Filter {
-                query: r#""body""#.to_string(),
-                values: vec!["foo".to_string(), "example".to_string()],
-                operation: Operation::Contains,
+                query: "user.login".to_string(),
+                values: vec!["kaplanelad".to_string()],
+                ..Filter::default()
+            },
+            Filter {
+                query: "labels[].name".to_string(),
+                values: vec!["p1".to_string()],
+                ..Filter::default()
             },
+        ];
+        assert_debug_snapshot!(is_match_filters(&json, &filters));
+    }
+
+    #[test]
+    #[cfg(feature = "jql")]
+    fn can_match_jql_queries() {
+        let json = json!({
+            "user": {"login": "kaplanelad"},
+            "labels": [{"name": "bug"}, {"name": "p1"}],
+        });
+        let filters = vec![
             Filter {
                 query: r#""user"."login""#.to_string(),
-                values: vec!["foo".to_string(), "kaplanelad".to_string()],
-                operation: Operation::Equal,
+                values: vec!["kaplanelad".to_string()],
+                ..Filter::default()
             },
             Filter {
                 query: r#""labels"|={"name"}."name""#.to_string(),
-                values: vec!["foo".to_string(), "label-1".to_string()],
+                values: vec!["p1".to_string()],
+                ..Filter::default()
+            },
+        ];
+        assert!(is_match_filters(&json, &filters).unwrap());
+    }
+
+    #[test]
+    #[cfg(not(feature = "jql"))]
+    fn can_explain_jql_requires_feature() {
+        let filters = vec![Filter {
+            query: r#""user"."login""#.to_string(),
+            values: vec!["kaplanelad".to_string()],
+            ..Filter::default()
+        }];
+        let err = is_match_filters(&json!({"user": {"login": "kaplanelad"}}), &filters)
+            .map_err(|e| format!("{:#}", e))
+            .unwrap_err();
+        assert!(err.contains("requires the jql feature"));
+    }
+
+    #[test]
+    fn can_cache_query_results() {
+        let json = json!({"title": "fix typo"});
+        let mut cache = QueryCache::new(&json);
+
+        assert_debug_snapshot!(cache.query("title"));
+        assert_debug_snapshot!(cache.query("title"));
+        assert_eq!(cache.results.len(), 1);
+        assert!(cache.query("body").is_err());
+        assert_eq!(cache.results.len(), 1);
+    }
+
+    #[test]
+    fn can_report_filters() {
+        let json = json!({
+            "title": "fix typo",
+            "user" : {
+                "login": "kaplanelad"
+            }
+        });
+        let filters = vec![
+            Filter {
+                query: "user.login".to_string(),
+                values: vec!["kaplanelad".to_string()],
                 operation: Operation::Equal,
+                ..Filter::default()
             },
             Filter {
-                query: r#""labels"|={"name"}."name""#.to_string(),
-                values: vec!["foo".to_string(), "label".to_string()],
+                name: Some("feature-title".to_string()),
+                query: "title".to_string(),
+                values: vec!["feature".to_string()],
                 operation: Operation::Contains,
+                ..Filter::default()
             },
         ];
-        assert_debug_snapshot!(is_match_filters(&json, &filter));
+        let report = match_report(&json, &filters).unwrap();
+        assert_debug_snapshot!(report);
+        assert!(!report.is_match());
+        assert_eq!(report.failed().count(), 1);
+
+        let err = match_report(&json!({"user": {"login": "kaplanelad"}}), &filters).unwrap_err();
+        assert_debug_snapshot!(err.to_string());
+    }
+
+    #[test]
+    fn can_select_fields() {
+        let json = json!({
+            "title": "fix typo",
+            "body": "long description",
+            "user" : {
+                "login": "kaplanelad",
+                "id": 1,
+            },
+            "labels": [
+                {
+                    "name": "label-1",
+                },
+            ],
+        });
+        assert_debug_snapshot!(select(
+            &json,
+            &[
+                "title".to_string(),
+                "user.login".to_string(),
+                "labels[].name".to_string(),
+            ]
+        ));
+        assert!(select(&json, &["milestone".to_string()]).is_err());
+    }
+
+    #[test]
+    fn can_filter_array() {
+        let items = vec![
+            json!({"user": {"login": "kaplanelad"}, "id": 1}),
+            json!({"user": {"login": "dependabot"}, "id": 2}),
+            json!({"id": 3}),
+        ];
+        let filters = vec![Filter {
+            query: "user.login".to_string(),
+            values: vec!["kaplanelad".to_string()],
+            operation: Operation::Equal,
+            ..Filter::default()
+        }];
+        assert!(filter_array(&items, &filters).is_err());
+        assert_debug_snapshot!(filter_array_with_policy(
+            &items,
+            &filters,
+            ErrorPolicy::Skip
+        ));
+        assert_debug_snapshot!(filter_array_with_policy(
+            &items,
+            &filters,
+            ErrorPolicy::Keep
+        ));
+    }
+
+    #[test]
+    fn can_coerce_values() {
+        let json = json!({
+            "number": 5,
+            "draft": true,
+            "reviewers": [1, 2],
+        });
+        let filter = |query: &str, values: Vec<&str>, coerce: bool| {
+            vec![Filter {
+                query: query.to_string(),
+                values: values.iter().map(ToString::to_string).collect(),
+                operation: Operation::Equal,
+                coerce,
+                ..Filter::default()
+            }]
+        };
+        assert!(is_match_filters(&json, &filter("number", vec!["5"], false)).is_err());
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter("number", vec!["5.0"], true)
+        ));
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter("draft", vec!["true"], true)
+        ));
+        assert_debug_snapshot!(is_match_filters(
+            &json,
+            &filter("reviewers", vec!["2"], true)
+        ));
+    }
+
+    #[test]
+    fn can_match_in_set() {
+        let prs = [
+            json!({"number": 1, "user": {"login": "kaplanelad"}}),
+            json!({"number": 2, "user": {"login": "dependabot"}}),
+            json!({"number": 3, "user": {"login": "jondot"}}),
+        ];
+        let documents = HashMap::from([(
+            "team".to_string(),
+            json!({"members": [{"login": "kaplanelad"}, {"login": "jondot"}]}),
+        )]);
+        let filters: Vec<Filter> = serde_json::from_value(json!([{
+            "query": "user.login",
+            "operation": "in_set",
+            "set": {"document": "team", "query": "members[].login"},
+        }]))
+        .unwrap();
+
+        let resolved = resolve_sets(&filters, &documents).unwrap();
+        assert_debug_snapshot!(filter_array(&prs, &resolved).map(|items| items
+            .iter()
+            .map(|item| item["number"].clone())
+            .collect::<Vec<_>>()));
+        assert!(resolve_sets(&filters, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn can_select_top_n() {
+        let prs = [
+            json!({"number": 1, "updated_at": "2022-10-20T09:00:00Z"}),
+            json!({"number": 2}),
+            json!({"number": 3, "updated_at": "2022-10-21T09:00:00Z"}),
+            json!({"number": 4, "updated_at": "2022-10-19T09:00:00Z"}),
+            json!({"number": 5, "updated_at": "2022-10-21T09:00:00Z"}),
+        ];
+        let numbers = |items: Vec<Value>| {
+            items
+                .iter()
+                .map(|item| item["number"].clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_debug_snapshot!(numbers(top_n(&prs, "updated_at", SortOrder::Desc, 3)));
+        assert_debug_snapshot!(numbers(top_n(&prs, "updated_at", SortOrder::Asc, 10)));
+        assert_debug_snapshot!(numbers(top_n(&prs, "number", SortOrder::Desc, 2)));
+    }
+
+    #[test]
+    fn can_inspect_sample() {
+        let sample = json!({
+            "number": 1,
+            "draft": false,
+            "merged_at": null,
+            "user": {"login": "kaplanelad"},
+            "labels": [
+                {"name": "bug"},
+                {"name": "p1", "color": "red"},
+            ],
+            "commits": [{"files": [{"filename": "README.md"}]}],
+        });
+
+        assert_debug_snapshot!(inspect(&sample)
+            .iter()
+            .map(|s| format!(
+                "{} | {} | {}",
+                s.query,
+                s.jql.as_deref().unwrap_or("-"),
+                s.kind
+            ))
+            .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_dedup_by_query() {
+        let pages = [
+            json!({"id": 1, "title": "first"}),
+            json!({"id": 2, "title": "second"}),
+            json!({"id": 2, "title": "second again"}),
+            json!({"id": "1", "title": "string id"}),
+            json!({"id": 1, "title": "first again"}),
+        ];
+
+        assert_debug_snapshot!(dedup_by(&pages, "id"));
+        assert!(dedup_by(&[json!({"number": 1})], "id").is_err());
+    }
+
+    #[test]
+    fn can_transform_values() {
+        let json = json!({
+            "ref": "refs/heads/Main",
+            "redirect": "https%3A%2F%2Fgithub.com%2Frusty-ferris-club",
+            "payload": "{\"labels\": [\"bug\", \"p1\"], \"sender\": {\"login\": \"kaplanelad\"}}",
+            "content": "  d2VicWw=\n",
+        });
+        let filters: Vec<Filter> = serde_json::from_value(json!([
+            {
+                "query": "ref",
+                "operation": "=",
+                "values": ["main"],
+                "transforms": [{"strip_prefix": "refs/heads/"}, "lowercase"],
+            },
+            {
+                "query": "redirect",
+                "operation": "=",
+                "values": ["https://github.com/rusty-ferris-club"],
+                "transforms": ["url_decode"],
+            },
+            {
+                "query": "payload",
+                "operation": "=",
+                "values": ["kaplanelad"],
+                "transforms": ["json_parse", {"query": "sender.login"}],
+            },
+            {
+                "query": "payload",
+                "operation": "=",
+                "values": ["p1"],
+                "transforms": ["json_parse", {"query": "labels"}],
+            },
+            {
+                "query": "content",
+                "operation": "=",
+                "values": ["webql"],
+                "transforms": ["trim", "base64_decode"],
+            },
+        ]))
+        .unwrap();
+        assert!(is_match_filters(&json, &filters).unwrap());
+
+        let invalid = Filter {
+            transforms: vec![Transform::JsonParse],
+            ..Filter::query("ref").equals(["main"])
+        };
+        assert_debug_snapshot!(is_match_filters(&json, &[invalid]).map_err(|e| format!("{:#}", e)));
+    }
+
+    #[test]
+    fn can_normalize_strings() {
+        let filter = |values: Vec<&str>, operation: Operation| Filter {
+            query: "".to_string(),
+            values: values.iter().map(ToString::to_string).collect(),
+            operation,
+            normalize: Some(Normalization::Nfkc),
+            ignore_case: true,
+            ..Filter::default()
+        };
+        assert_debug_snapshot!(is_match_string(
+            "Caf\u{e9}",
+            &filter(vec!["cafe\u{301}"], Operation::Equal)
+        ));
+        assert_debug_snapshot!(is_match_string(
+            "Release \u{ff37}\u{ff45}\u{ff42}\u{ff31}\u{ff2c}",
+            &filter(vec!["webql"], Operation::Contains)
+        ));
+        assert_debug_snapshot!(is_match_string(
+            "Caf\u{e9}",
+            &Filter {
+                normalize: None,
+                ignore_case: false,
+                ..filter(vec!["cafe\u{301}"], Operation::Equal)
+            }
+        ));
+    }
+
+    #[test]
+    fn is_relative_date_match_string() {
+        let filter = |operation: Operation| Filter {
+            query: "".to_string(),
+            values: vec!["7d".to_string()],
+            operation,
+            ..Filter::default()
+        };
+        let yesterday = (Utc::now() - Duration::days(1)).to_rfc3339();
+        let last_month = (Utc::now() - Duration::days(30)).to_rfc3339();
+        assert_debug_snapshot!(is_match_string(&yesterday, &filter(Operation::Within)));
+        assert_debug_snapshot!(is_match_string(&last_month, &filter(Operation::Within)));
+        assert_debug_snapshot!(is_match_string(&yesterday, &filter(Operation::OlderThan)));
+        assert_debug_snapshot!(is_match_string(&last_month, &filter(Operation::OlderThan)));
+    }
+
+    #[test]
+    fn can_match_not_equal() {
+        let json = json!({"user": {"login": "kaplanelad"}});
+        let filter = |values: &[&str], negate: bool| {
+            vec![Filter {
+                operation: Operation::NotEqual,
+                negate,
+                ..Filter::query("user.login").equals(values.to_vec())
+            }]
+        };
+        assert!(!is_match_filters(&json, &filter(&["kaplanelad", "jondot"], false)).unwrap());
+        assert!(is_match_filters(&json, &filter(&["jondot"], false)).unwrap());
+        assert!(!is_match_filters(&json, &filter(&["jondot"], true)).unwrap());
+    }
+
+    #[test]
+    fn can_match_greater_or_equal() {
+        let json = json!({
+            "comments": 5,
+            "additions": "12",
+            "merged_at": "2022-10-20T10:00:00Z",
+            "title": "fix",
+        });
+        let filter = |query: &str, value: &str| {
+            vec![Filter {
+                operation: Operation::GreaterOrEqual,
+                ..Filter::query(query).equals([value])
+            }]
+        };
+        assert!(is_match_filters(&json, &filter("comments", "5")).unwrap());
+        assert!(!is_match_filters(&json, &filter("comments", "5.5")).unwrap());
+        assert!(is_match_filters(&json, &filter("additions", "9")).unwrap());
+        assert!(is_match_filters(&json, &filter("merged_at", "2022-10-19T00:00:00Z")).unwrap());
+        assert!(!is_match_filters(&json, &filter("merged_at", "2022-11-01T00:00:00Z")).unwrap());
+        assert!(!is_match_filters(&json, &filter("title", "a")).unwrap());
+    }
+
+    #[test]
+    fn can_match_regexp() {
+        let json = json!({"head": {"ref": "release/v12"}, "labels": [{"name": "bug"}]});
+        let filter = |query: &str, values: &[&str], ignore_case: bool| {
+            vec![Filter {
+                operation: Operation::Regexp,
+                ignore_case,
+                ..Filter::query(query).equals(values.to_vec())
+            }]
+        };
+        assert!(is_match_filters(&json, &filter("head.ref", &[r"^release/v\d+$"], false)).unwrap());
+        assert!(!is_match_filters(&json, &filter("head.ref", &["^main$"], false)).unwrap());
+        assert!(!is_match_filters(&json, &filter("head.ref", &["(release"], false)).unwrap());
+        assert!(is_match_filters(&json, &filter("labels[].name", &["^bu"], false)).unwrap());
+        assert!(is_match_filters(&json, &filter("head.ref", &["^RELEASE/"], true)).unwrap());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn can_par_filter_array() {
+        let items = (0..100)
+            .map(|id| {
+                let label = if id % 10 == 0 { "bug" } else { "docs" };
+                json!({"id": id, "label": label})
+            })
+            .collect::<Vec<_>>();
+        let filters = vec![Filter {
+            query: "label".to_string(),
+            values: vec!["bug".to_string()],
+            operation: Operation::Equal,
+            ..Filter::default()
+        }];
+
+        let matched = super::par_filter_array(&items, &filters).unwrap();
+        assert_eq!(matched, filter_array(&items, &filters).unwrap());
+        assert_debug_snapshot!(matched.iter().map(|item| &item["id"]).collect::<Vec<_>>());
     }
 }
@@ -0,0 +1,108 @@
+//! Apply filters to JSON and YAML documents on disk
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use super::filter_array;
+use crate::data::Filter;
+
+/// Load a JSON or YAML document from disk and return the items that match
+/// all the filters. When the document is an array each element is filtered,
+/// otherwise the document itself is the only item. YAML files (`.yaml` or
+/// `.yml`) require the `yaml` feature
+///
+/// # Arguments
+/// * `path` - Document path
+/// * `filters` - List of filter queries
+///
+/// # Errors
+/// - When the file could not be read or parsed
+/// - When [`Filter`] query is invalid for one of the items
+pub fn filter_file<P: AsRef<Path>>(path: P, filters: &[Filter]) -> Result<Vec<Value>> {
+    let document = read_document(path.as_ref())?;
+    match document {
+        Value::Array(items) => filter_array(&items, filters),
+        document => filter_array(&[document], filters),
+    }
+}
+
+/// Read and parse the document by the file extension
+///
+/// # Errors
+/// - When the file could not be read or parsed
+fn read_document(path: &Path) -> Result<Value> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("could not read file {}", path.display()))?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml" | "yml") => parse_yaml(&content)
+            .with_context(|| format!("could not parse yaml file {}", path.display())),
+        _ => serde_json::from_str(&content)
+            .with_context(|| format!("could not parse json file {}", path.display())),
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn parse_yaml(content: &str) -> Result<Value> {
+    Ok(serde_yaml::from_str(content)?)
+}
+
+#[cfg(not(feature = "yaml"))]
+fn parse_yaml(_content: &str) -> Result<Value> {
+    anyhow::bail!("yaml files require the yaml feature")
+}
+
+#[cfg(test)]
+mod test_fs {
+
+    use std::{env, fs, path::PathBuf};
+
+    use insta::assert_debug_snapshot;
+
+    use super::filter_file;
+    use crate::data::{Filter, Operation};
+
+    fn write_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("webql-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn filters() -> Vec<Filter> {
+        vec![Filter {
+            query: "label".to_string(),
+            values: vec!["bug".to_string()],
+            operation: Operation::Equal,
+            ..Filter::default()
+        }]
+    }
+
+    #[test]
+    fn can_filter_json_file() {
+        let array = write_file(
+            "array.json",
+            r#"[{"id": 1, "label": "bug"}, {"id": 2, "label": "docs"}]"#,
+        );
+        let object = write_file("object.json", r#"{"id": 3, "label": "docs"}"#);
+
+        assert_debug_snapshot!(filter_file(&array, &filters()));
+        assert_debug_snapshot!(filter_file(&object, &filters()));
+        assert!(filter_file(env::temp_dir().join("webql-not-found.json"), &filters()).is_err());
+
+        fs::remove_file(array).unwrap();
+        fs::remove_file(object).unwrap();
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn can_filter_yaml_file() {
+        let path = write_file(
+            "array.yaml",
+            "- id: 1\n  label: bug\n- id: 2\n  label: docs\n",
+        );
+
+        assert_debug_snapshot!(filter_file(&path, &filters()));
+        fs::remove_file(path).unwrap();
+    }
+}
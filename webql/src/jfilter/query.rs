@@ -0,0 +1,212 @@
+//! Builtin query evaluator for the common cases: dot paths (`user.login`),
+//! array index (`labels[0].name`) and array pluck (`labels[].name`). It
+//! returns friendlier errors than jql, which stays available for advanced
+//! queries, see [`is_jql`]
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+/// Characters that only exist in jql queries
+const JQL_MARKERS: &[&str] = &["\"", "|", "{", "..", "!"];
+
+/// Single step of a builtin query
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// Object field
+    Key(String),
+    /// Array element
+    Index(usize),
+    /// Apply the rest of the query on each array element
+    Pluck,
+}
+
+/// Return true when the query uses jql syntax, e.g. `"user"."login"`, and
+/// false for builtin queries, e.g. `user.login`
+#[must_use]
+pub fn is_jql(query: &str) -> bool {
+    query.starts_with('.') || JQL_MARKERS.iter().any(|marker| query.contains(marker))
+}
+
+/// Run the builtin query on the json [`Value`]
+///
+/// # Arguments
+/// * `data` - Event data
+/// * `query` - Builtin query, e.g. `labels[].name`
+///
+/// # Errors
+/// - When the query is invalid
+/// - When a field or index is not found, the error has the failing path
+pub fn evaluate(data: &Value, query: &str) -> Result<Value> {
+    let steps = parse(query)?;
+    evaluate_steps(data, &steps, "")
+}
+
+/// Parse the query to [`Step`]s
+///
+/// # Errors
+/// - When the query is empty or a bracket is invalid
+fn parse(query: &str) -> Result<Vec<Step>> {
+    if query.trim().is_empty() {
+        bail!("query is empty");
+    }
+
+    let mut steps = vec![];
+    for segment in query.split('.') {
+        let (name, mut brackets) = match segment.find('[') {
+            Some(at) => segment.split_at(at),
+            None => (segment, ""),
+        };
+        if name.is_empty() && brackets.is_empty() {
+            bail!("query `{}` has an empty field name", query);
+        }
+        if !name.is_empty() {
+            steps.push(Step::Key(name.to_string()));
+        }
+
+        while !brackets.is_empty() {
+            let Some(end) = brackets.find(']') else {
+                bail!("query `{}` has an unclosed `[`", query);
+            };
+            let inner = &brackets[1..end];
+            if inner.is_empty() {
+                steps.push(Step::Pluck);
+            } else {
+                let index = inner.parse().map_err(|_| {
+                    anyhow::anyhow!(
+                        "query `{}` has an invalid index `{}`, expected a number or `[]`",
+                        query,
+                        inner
+                    )
+                })?;
+                steps.push(Step::Index(index));
+            }
+            brackets = &brackets[end + 1..];
+            if !brackets.is_empty() && !brackets.starts_with('[') {
+                bail!("query `{}` has unexpected `{}` after `]`", query, brackets);
+            }
+        }
+    }
+    Ok(steps)
+}
+
+/// Evaluate the steps on the value
+///
+/// # Arguments
+/// * `value` - Current value
+/// * `steps` - Remaining steps
+/// * `path` - Path of the current value, for errors
+///
+/// # Errors
+/// - When a field or index is not found
+fn evaluate_steps(value: &Value, steps: &[Step], path: &str) -> Result<Value> {
+    let Some((step, rest)) = steps.split_first() else {
+        return Ok(value.clone());
+    };
+
+    match (step, value) {
+        (Step::Key(key), Value::Object(map)) => match map.get(key) {
+            Some(field) => evaluate_steps(field, rest, &join(path, key)),
+            None => bail!(
+                "field `{}` not found at `{}`, available fields: {}",
+                key,
+                display_path(path),
+                map.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+        },
+        (Step::Index(index), Value::Array(items)) => match items.get(*index) {
+            Some(item) => evaluate_steps(item, rest, &format!("{}[{}]", path, index)),
+            None => bail!(
+                "index {} out of bounds at `{}`, length is {}",
+                index,
+                display_path(path),
+                items.len()
+            ),
+        },
+        (Step::Pluck, Value::Array(items)) => Ok(Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| evaluate_steps(item, rest, &format!("{}[{}]", path, index)))
+                .collect::<Result<Vec<_>>>()?,
+        )),
+        (Step::Key(key), other) => bail!(
+            "expected an object at `{}` to get field `{}`, found {}",
+            display_path(path),
+            key,
+            type_name(other)
+        ),
+        (Step::Index(_) | Step::Pluck, other) => bail!(
+            "expected an array at `{}`, found {}",
+            display_path(path),
+            type_name(other)
+        ),
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "<root>"
+    } else {
+        path
+    }
+}
+
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod test_query {
+
+    use insta::assert_debug_snapshot;
+    use serde_json::json;
+
+    use super::{evaluate, is_jql};
+
+    #[test]
+    fn can_detect_jql_queries() {
+        assert!(is_jql(r#""user"."login""#));
+        assert!(is_jql(r#""labels"|={"name"}"#));
+        assert!(!is_jql("user.login"));
+        assert!(!is_jql("labels[].name"));
+    }
+
+    #[test]
+    fn can_evaluate_queries() {
+        let json = json!({
+            "user": {"login": "kaplanelad"},
+            "labels": [{"name": "bug"}, {"name": "p1"}],
+        });
+
+        assert_debug_snapshot!(evaluate(&json, "user.login"));
+        assert_debug_snapshot!(evaluate(&json, "labels[1].name"));
+        assert_debug_snapshot!(evaluate(&json, "labels[].name"));
+    }
+
+    #[test]
+    fn can_explain_errors() {
+        let json = json!({
+            "user": {"login": "kaplanelad", "id": 1},
+            "labels": [{"name": "bug"}],
+        });
+
+        assert_debug_snapshot!(evaluate(&json, "user.name").map_err(|e| e.to_string()));
+        assert_debug_snapshot!(evaluate(&json, "labels[3]").map_err(|e| e.to_string()));
+        assert_debug_snapshot!(evaluate(&json, "user.login.first").map_err(|e| e.to_string()));
+        assert_debug_snapshot!(evaluate(&json, "labels[x]").map_err(|e| e.to_string()));
+    }
+}
@@ -10,5 +10,15 @@
 //!
 pub mod vendor;
 
+pub mod bus;
 pub mod data;
+pub mod dedup;
+#[cfg(feature = "edits")]
+pub mod edits;
+pub mod export;
 pub mod jfilter;
+pub mod pipeline;
+pub mod report;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+mod utils;
@@ -0,0 +1,481 @@
+//! Post processing of fetched events
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    data::{Event, EventKind, Filter},
+    jfilter, utils,
+};
+
+/// Row data key that holds the number of collapsed events
+pub const OCCURRENCES_KEY: &str = "occurrences";
+
+/// Order of the returned events
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventOrder {
+    /// Keep the order returned by the vendor
+    #[default]
+    Fetched,
+    /// Oldest first. ties are broken by kind and id so the order is stable
+    /// across runs
+    DateAsc,
+    /// Newest first. ties are broken by kind and id so the order is stable
+    /// across runs
+    DateDesc,
+}
+
+/// Sort the events by the given [`EventOrder`]
+///
+/// # Arguments
+/// * `events` - List of [`Event`]
+/// * `order` - [`EventOrder`]
+pub fn sort_events(events: &mut [Event], order: EventOrder) {
+    match order {
+        EventOrder::Fetched => {}
+        EventOrder::DateAsc => events.sort_by(compare_events),
+        EventOrder::DateDesc => events.sort_by(|a, b| compare_events(b, a)),
+    }
+}
+
+/// Emit an alert when more than `threshold` events match the filters within
+/// the window, e.g. more than 10 pull requests labeled `sev1` in the last
+/// hour
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ThresholdRule {
+    /// Rule name, used as the alert id
+    pub name: String,
+    /// Events that are counted, all the events when empty
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    /// Alert when the count is greater than the threshold
+    pub threshold: usize,
+    /// Count only events from the last duration, e.g. `1h`. when not set all
+    /// the events of the poll are counted
+    #[serde(default)]
+    pub window: Option<String>,
+    /// Priority of the alert event
+    #[serde(default)]
+    pub priority: usize,
+}
+
+/// Evaluate the threshold rules on the events and return an
+/// [`EventKind::Alert`] event for each rule that was exceeded. Events that
+/// fail the filters evaluation, e.g. when a query is not found, are not
+/// counted
+///
+/// # Arguments
+/// * `events` - List of [`Event`]
+/// * `rules` - List of [`ThresholdRule`]
+/// * `now` - End of the window
+///
+/// # Errors
+/// - When a rule window is not a valid duration
+pub fn threshold_alerts(
+    events: &[Event],
+    rules: &[ThresholdRule],
+    now: DateTime<Utc>,
+) -> Result<Vec<Event>> {
+    let mut alerts = vec![];
+    for rule in rules {
+        let since = rule
+            .window
+            .as_deref()
+            .map(utils::parse_duration)
+            .transpose()?
+            .map(|window| now - window);
+
+        let matched = events
+            .iter()
+            .filter(|event| match (since, event.date) {
+                (Some(since), Some(date)) => date >= since,
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .filter(|event| {
+                jfilter::is_match_filters(&event.row_data, &rule.filters).unwrap_or(false)
+            })
+            .map(|event| event.id.as_str())
+            .collect::<Vec<_>>();
+
+        if matched.len() > rule.threshold {
+            alerts.push(Event {
+                kind: EventKind::Alert,
+                id: rule.name.clone(),
+                parent_event_id: None,
+                name: format!(
+                    "{}: {} events, more than {}",
+                    rule.name,
+                    matched.len(),
+                    rule.threshold
+                ),
+                link: None,
+                date: Some(now),
+                priority: rule.priority,
+                row_data: json!({
+                    "rule": rule.name,
+                    "count": matched.len(),
+                    "threshold": rule.threshold,
+                    "window": rule.window,
+                    "event_ids": matched,
+                }),
+                annotations: HashMap::new(),
+            });
+        }
+    }
+    Ok(alerts)
+}
+
+/// Annotate the events with the value computed by `annotator`, events for
+/// which `annotator` returns `None` are left untouched. Use it for enrichers
+/// that attach metadata to the events without changing the vendor row data
+///
+/// # Arguments
+/// * `events` - List of [`Event`]
+/// * `key` - Annotation name
+/// * `annotator` - Compute the annotation value of an event
+pub fn annotate<F>(events: &mut [Event], key: &str, annotator: F)
+where
+    F: Fn(&Event) -> Option<Value>,
+{
+    for event in events {
+        if let Some(value) = annotator(event) {
+            event.annotate(key, value);
+        }
+    }
+}
+
+/// Consumer of events ordered by importance, see [`into_priority_queue`]
+#[derive(Debug, Default)]
+pub struct PriorityQueue {
+    heap: BinaryHeap<Queued>,
+}
+
+/// Heap entry. the greatest entry is the most important event
+#[derive(Debug)]
+struct Queued(Event);
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (&self.0, &other.0);
+        // lower priority value is more important, same as iCalendar priority
+        b.priority
+            .cmp(&a.priority)
+            // older events waited longer, events without date come last
+            .then_with(|| match (a.date, b.date) {
+                (Some(a_date), Some(b_date)) => b_date.cmp(&a_date),
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => Ordering::Equal,
+            })
+            .then_with(|| format!("{:?}", b.kind).cmp(&format!("{:?}", a.kind)))
+            .then_with(|| b.id.cmp(&a.id))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PriorityQueue {
+    /// Add an event to the queue
+    pub fn push(&mut self, event: Event) {
+        self.heap.push(Queued(event));
+    }
+
+    /// Remove and return the most important event
+    pub fn pop(&mut self) -> Option<Event> {
+        self.heap.pop().map(|queued| queued.0)
+    }
+
+    /// Remove and return up to `n` most important events, most important
+    /// first
+    ///
+    /// # Arguments
+    /// * `n` - Maximum number of events to return
+    pub fn pop_batch(&mut self, n: usize) -> Vec<Event> {
+        let mut batch = Vec::with_capacity(n.min(self.heap.len()));
+        while batch.len() < n {
+            match self.pop() {
+                Some(event) => batch.push(event),
+                None => break,
+            }
+        }
+        batch
+    }
+
+    /// Return the most important event without removing it
+    #[must_use]
+    pub fn peek(&self) -> Option<&Event> {
+        self.heap.peek().map(|queued| &queued.0)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+/// Build a [`PriorityQueue`] from the events, so triage consumers can pull
+/// the next most important events without sorting the full list again. The
+/// most important event has the lowest priority value, ties are broken by
+/// the oldest date
+///
+/// # Arguments
+/// * `events` - List of [`Event`]
+pub fn into_priority_queue(events: Vec<Event>) -> PriorityQueue {
+    PriorityQueue {
+        heap: events.into_iter().map(Queued).collect(),
+    }
+}
+
+/// Collapse consecutive comments with the same body from the same author on
+/// the same parent into one event. The first event is kept and the number of
+/// occurrences is set in its row data under [`OCCURRENCES_KEY`]. Useful for
+/// bots that post the same comment on every push.
+///
+/// # Arguments
+/// * `events` - List of [`Event`]
+pub fn collapse_duplicate_comments(events: Vec<Event>) -> Vec<Event> {
+    let mut collapsed: Vec<Event> = Vec::with_capacity(events.len());
+    for event in events {
+        if let Some(previous) = collapsed.last_mut() {
+            if is_duplicate_comment(previous, &event) {
+                let occurrences = previous
+                    .row_data
+                    .get(OCCURRENCES_KEY)
+                    .and_then(Value::as_u64)
+                    .unwrap_or(1);
+                if let Some(row_data) = previous.row_data.as_object_mut() {
+                    row_data.insert(OCCURRENCES_KEY.to_string(), Value::from(occurrences + 1));
+                }
+                continue;
+            }
+        }
+        collapsed.push(event);
+    }
+    collapsed
+}
+
+/// Check if both events are comments with the same body from the same author
+/// on the same parent. other kinds, e.g. commits or edits, are never collapsed
+fn is_duplicate_comment(a: &Event, b: &Event) -> bool {
+    fn author(e: &Event) -> Option<&str> {
+        e.row_data.pointer("/user/login").and_then(Value::as_str)
+    }
+    fn body(e: &Event) -> Option<&str> {
+        e.row_data.get("body").and_then(Value::as_str)
+    }
+
+    is_comment(&a.kind)
+        && a.kind == b.kind
+        && a.parent_event_id == b.parent_event_id
+        && author(a).is_some()
+        && author(a) == author(b)
+        && body(a).is_some()
+        && body(a) == body(b)
+}
+
+/// Check if the event kind is a pull request comment
+#[cfg(feature = "github")]
+fn is_comment(kind: &EventKind) -> bool {
+    matches!(kind, EventKind::PrComment | EventKind::PrReviewComment)
+}
+
+#[cfg(not(feature = "github"))]
+fn is_comment(_kind: &EventKind) -> bool {
+    false
+}
+
+/// Compare events by date, kind and id
+fn compare_events(a: &Event, b: &Event) -> Ordering {
+    a.date
+        .cmp(&b.date)
+        .then_with(|| format!("{:?}", a.kind).cmp(&format!("{:?}", b.kind)))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+#[cfg(test)]
+mod test_pipeline {
+
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, Utc};
+    use insta::assert_debug_snapshot;
+    use serde_json::{json, Value};
+
+    use super::{
+        annotate, collapse_duplicate_comments, into_priority_queue, sort_events, threshold_alerts,
+        EventOrder, ThresholdRule,
+    };
+    use crate::data::{Event, EventKind, Filter, Operation};
+
+    fn event(id: &str, date: &str) -> Event {
+        Event {
+            kind: EventKind::Edited,
+            id: id.to_string(),
+            parent_event_id: None,
+            name: String::new(),
+            link: None,
+            date: date.parse().ok(),
+            priority: 1,
+            row_data: Value::Null,
+            annotations: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn can_sort_events() {
+        let mut events = vec![
+            event("2", "2022-10-20T10:00:00Z"),
+            event("3", "2022-10-21T10:00:00Z"),
+            event("1", "2022-10-20T10:00:00Z"),
+        ];
+
+        sort_events(&mut events, EventOrder::DateAsc);
+        assert_debug_snapshot!(events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>());
+
+        sort_events(&mut events, EventOrder::DateDesc);
+        assert_debug_snapshot!(events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[cfg(feature = "github")]
+    fn can_collapse_duplicate_comments() {
+        let comment = |id: &str, login: &str, body: &str| Event {
+            kind: EventKind::PrComment,
+            id: id.to_string(),
+            parent_event_id: Some("1".to_string()),
+            name: body.to_string(),
+            link: None,
+            date: None,
+            priority: 1,
+            row_data: json!({"user": {"login": login}, "body": body}),
+            annotations: HashMap::new(),
+        };
+        let events = collapse_duplicate_comments(vec![
+            comment("1", "codecov", "coverage 80%"),
+            comment("2", "codecov", "coverage 80%"),
+            comment("3", "codecov", "coverage 80%"),
+            comment("4", "kaplanelad", "coverage 80%"),
+            comment("5", "codecov", "coverage 80%"),
+        ]);
+        assert_debug_snapshot!(events
+            .iter()
+            .map(|e| (e.id.as_str(), e.row_data.get("occurrences").cloned()))
+            .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_keep_duplicate_events_of_other_kinds() {
+        let edited = |id: &str| Event {
+            kind: EventKind::Edited,
+            parent_event_id: Some("1".to_string()),
+            row_data: json!({"user": {"login": "kaplanelad"}, "body": "fix typo"}),
+            ..event(id, "2022-10-20T10:00:00Z")
+        };
+        let events = collapse_duplicate_comments(vec![edited("1"), edited("2")]);
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn can_pop_batch_by_priority() {
+        let with_priority = |id: &str, date: &str, priority: usize| Event {
+            priority,
+            ..event(id, date)
+        };
+        let mut queue = into_priority_queue(vec![
+            with_priority("1", "2022-10-21T10:00:00Z", 2),
+            with_priority("2", "2022-10-22T10:00:00Z", 1),
+            with_priority("3", "", 1),
+            with_priority("4", "2022-10-20T10:00:00Z", 1),
+            with_priority("5", "2022-10-19T10:00:00Z", 3),
+        ]);
+
+        assert_eq!(queue.len(), 5);
+        assert_debug_snapshot!(queue
+            .pop_batch(2)
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect::<Vec<_>>());
+        assert_debug_snapshot!(queue
+            .pop_batch(10)
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect::<Vec<_>>());
+        assert!(queue.is_empty());
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn can_emit_threshold_alerts() {
+        let labeled = |id: &str, date: &str, label: &str| Event {
+            row_data: json!({ "labels": [label] }),
+            ..event(id, date)
+        };
+        let events = vec![
+            labeled("1", "2022-10-20T09:10:00Z", "sev1"),
+            labeled("2", "2022-10-20T09:20:00Z", "sev1"),
+            labeled("3", "2022-10-20T09:30:00Z", "sev2"),
+            labeled("4", "2022-10-20T07:00:00Z", "sev1"),
+        ];
+        let rule = |name: &str, window: Option<&str>| ThresholdRule {
+            name: name.to_string(),
+            filters: vec![Filter {
+                query: "labels".to_string(),
+                values: vec!["sev1".to_string()],
+                operation: Operation::Equal,
+                ..Filter::default()
+            }],
+            threshold: 2,
+            window: window.map(str::to_string),
+            priority: 1,
+        };
+        let now = "2022-10-20T10:00:00Z".parse().unwrap();
+
+        let alerts = threshold_alerts(
+            &events,
+            &[rule("sev1-poll", None), rule("sev1-last-hour", Some("1h"))],
+            now,
+        )
+        .unwrap();
+        assert_debug_snapshot!(alerts);
+        assert!(threshold_alerts(&events, &[rule("invalid", Some("1y"))], now).is_err());
+    }
+
+    #[test]
+    fn can_annotate_events() {
+        let mut events = vec![event("1", "2022-10-20T09:00:00Z"), event("2", "not a date")];
+        let opened: DateTime<Utc> = "2022-10-20T07:00:00Z".parse().unwrap();
+        annotate(&mut events, "age_hours", |event| {
+            event.date.map(|date| json!((date - opened).num_hours()))
+        });
+
+        assert_debug_snapshot!(events
+            .iter()
+            .map(|event| (event.id.as_str(), event.annotation("age_hours")))
+            .collect::<Vec<_>>());
+    }
+}
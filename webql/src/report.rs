@@ -0,0 +1,122 @@
+//! Run to run comparison of events, e.g. for "what changed since yesterday"
+//! digests. Events are identified across runs by their kind and id
+use std::collections::{HashMap, HashSet};
+
+use crate::data::Event;
+
+/// Event that exists in both runs with a different priority
+#[derive(Debug, Clone)]
+pub struct PriorityChange {
+    /// Event of the current run
+    pub event: Event,
+    /// Priority of the event in the previous run
+    pub previous_priority: usize,
+}
+
+/// Summary of the changes between two runs, see [`compare`]
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// Events of the current run that were not in the previous run
+    pub new: Vec<Event>,
+    /// Events of the previous run that are not in the current run
+    pub resolved: Vec<Event>,
+    /// Events of both runs with the same priority
+    pub still_open: Vec<Event>,
+    /// Events of both runs with a different priority
+    pub changed_priority: Vec<PriorityChange>,
+}
+
+impl Report {
+    /// Return true when nothing changed between the runs
+    #[must_use]
+    pub fn is_unchanged(&self) -> bool {
+        self.new.is_empty() && self.resolved.is_empty() && self.changed_priority.is_empty()
+    }
+}
+
+/// Compare the events of two runs. The order of the current run is kept,
+/// resolved events are in the order of the previous run
+///
+/// # Arguments
+/// * `previous` - Events of the previous run
+/// * `current` - Events of the current run
+#[must_use]
+pub fn compare(previous: &[Event], current: &[Event]) -> Report {
+    let previous_by_key = previous
+        .iter()
+        .map(|event| (key(event), event))
+        .collect::<HashMap<_, _>>();
+    let current_keys = current.iter().map(key).collect::<HashSet<_>>();
+
+    let mut report = Report::default();
+    for event in current {
+        match previous_by_key.get(&key(event)) {
+            None => report.new.push(event.clone()),
+            Some(before) if before.priority != event.priority => {
+                report.changed_priority.push(PriorityChange {
+                    event: event.clone(),
+                    previous_priority: before.priority,
+                });
+            }
+            Some(_) => report.still_open.push(event.clone()),
+        }
+    }
+    report.resolved = previous
+        .iter()
+        .filter(|event| !current_keys.contains(&key(event)))
+        .cloned()
+        .collect();
+    report
+}
+
+/// Identity of the event across runs
+fn key(event: &Event) -> (String, &str) {
+    (format!("{:?}", event.kind), event.id.as_str())
+}
+
+#[cfg(test)]
+mod test_report {
+
+    use std::collections::HashMap;
+
+    use insta::assert_debug_snapshot;
+    use serde_json::Value;
+
+    use super::compare;
+    use crate::data::{Event, EventKind};
+
+    fn event(id: &str, priority: usize) -> Event {
+        Event {
+            kind: EventKind::Edited,
+            id: id.to_string(),
+            parent_event_id: None,
+            name: String::new(),
+            link: None,
+            date: None,
+            priority,
+            row_data: Value::Null,
+            annotations: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn can_compare_runs() {
+        let previous = [event("1", 1), event("2", 1), event("3", 1)];
+        let current = [event("4", 1), event("3", 1), event("2", 2)];
+
+        let report = compare(&previous, &current);
+        let ids = |events: &[Event]| events.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        assert_debug_snapshot!((
+            ids(&report.new),
+            ids(&report.resolved),
+            ids(&report.still_open),
+            report
+                .changed_priority
+                .iter()
+                .map(|change| (change.event.id.as_str(), change.previous_priority))
+                .collect::<Vec<_>>(),
+        ));
+        assert!(!report.is_unchanged());
+        assert!(compare(&current, &current).is_unchanged());
+    }
+}
@@ -0,0 +1,260 @@
+//! Test helpers. require `test-util` feature flag on
+//!
+//! * Conformance checks for [`VendorSource`] implementations. Each check
+//!   starts a mock HTTP server, builds the source with the server base url and
+//!   makes sure the source follows the expected behavior.
+//! * Helpers for deterministic snapshot tests of events: normalize dates and
+//!   shuffle events with a seed to check that consumers don't rely on the
+//!   vendor order.
+use anyhow::{ensure, Result};
+use chrono::{DateTime, Duration, Utc};
+use httpmock::prelude::*;
+use serde_json::{json, Value};
+
+use crate::{
+    data::Event,
+    vendor::{Resource, VendorSource},
+};
+
+/// Replacement of normalized dates
+pub const DATE_PLACEHOLDER: &str = "DATE";
+
+/// Mocked resource path
+const RESOURCE_PATH: &str = "conformance/items";
+
+/// Paginated [`Resource`] served by the mock server
+fn resource() -> Resource {
+    Resource {
+        path: RESOURCE_PATH.to_string(),
+        query: vec![],
+        date_field: Some("updated_at".to_string()),
+        items_field: None,
+        accept: None,
+        newest_first: false,
+    }
+}
+
+/// Run all the conformance checks
+///
+/// # Arguments
+/// * `build` - Create the [`VendorSource`] for the given host
+///
+/// # Errors
+/// - When one of the checks failed
+pub fn run_all<S, F>(build: F) -> Result<()>
+where
+    S: VendorSource,
+    F: Fn(&str) -> Result<S>,
+{
+    check_pagination(&build)?;
+    check_since_filtering(&build)?;
+    check_retry(&build)?;
+    check_error_propagation(&build)?;
+    Ok(())
+}
+
+/// Check that the source walks all the pages until an empty page
+///
+/// # Errors
+/// - When not all the documents are returned
+pub fn check_pagination<S, F>(build: F) -> Result<()>
+where
+    S: VendorSource,
+    F: Fn(&str) -> Result<S>,
+{
+    let server = MockServer::start();
+    let now = Utc::now();
+    for (page, ids) in [("1", vec![1, 2]), ("2", vec![3])] {
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/{}", RESOURCE_PATH))
+                .query_param("page", page);
+            then.status(200).json_body(Value::Array(
+                ids.iter()
+                    .map(|id| json!({"id": id, "updated_at": now + Duration::minutes(1)}))
+                    .collect(),
+            ));
+        });
+    }
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/{}", RESOURCE_PATH))
+            .query_param("page", "3");
+        then.status(200).json_body(Value::Array(vec![]));
+    });
+
+    let documents = build(&server.base_url())?.fetch(&resource(), now)?;
+    ensure!(
+        documents.len() == 3,
+        "expected 3 documents from 2 pages, got {}",
+        documents.len()
+    );
+    Ok(())
+}
+
+/// Check that documents older than `since` are dropped
+///
+/// # Errors
+/// - When old documents are returned
+pub fn check_since_filtering<S, F>(build: F) -> Result<()>
+where
+    S: VendorSource,
+    F: Fn(&str) -> Result<S>,
+{
+    let server = MockServer::start();
+    let now = Utc::now();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/{}", RESOURCE_PATH))
+            .query_param("page", "1");
+        then.status(200).json_body(json!([
+            {"id": 1, "updated_at": now + Duration::minutes(1)},
+            {"id": 2, "updated_at": now - Duration::minutes(1)},
+        ]));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/{}", RESOURCE_PATH))
+            .query_param("page", "2");
+        then.status(200).json_body(Value::Array(vec![]));
+    });
+
+    let documents = build(&server.base_url())?.fetch(&resource(), now)?;
+    ensure!(
+        documents.len() == 1,
+        "expected 1 document updated after since, got {}",
+        documents.len()
+    );
+    Ok(())
+}
+
+/// Check that a transient server error is retried before giving up
+///
+/// # Errors
+/// - When the request is not retried
+pub fn check_retry<S, F>(build: F) -> Result<()>
+where
+    S: VendorSource,
+    F: Fn(&str) -> Result<S>,
+{
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path(format!("/{}", RESOURCE_PATH));
+        then.status(503);
+    });
+
+    let result = build(&server.base_url())?.fetch(&resource(), Utc::now());
+    ensure!(
+        mock.hits() > 1,
+        "expected the request to be retried, got {} hits",
+        mock.hits()
+    );
+    ensure!(
+        result.is_err(),
+        "expected an error after all retries failed"
+    );
+    Ok(())
+}
+
+/// Check that a non-success response is returned as an error
+///
+/// # Errors
+/// - When the source hides the error
+pub fn check_error_propagation<S, F>(build: F) -> Result<()>
+where
+    S: VendorSource,
+    F: Fn(&str) -> Result<S>,
+{
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/{}", RESOURCE_PATH));
+        then.status(401)
+            .json_body(json!({"message": "Bad credentials"}));
+    });
+
+    let result = build(&server.base_url())?.fetch(&resource(), Utc::now());
+    ensure!(result.is_err(), "expected unauthorized response to fail");
+    Ok(())
+}
+
+/// Replace all the RFC 3339 date strings in the value with
+/// [`DATE_PLACEHOLDER`]
+pub fn normalize_dates(value: &mut Value) {
+    match value {
+        Value::String(s) if s.parse::<DateTime<Utc>>().is_ok() => {
+            *s = DATE_PLACEHOLDER.to_string();
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize_dates),
+        Value::Object(map) => map.values_mut().for_each(normalize_dates),
+        _ => {}
+    }
+}
+
+/// Remove the event dates and normalize the dates of the row data
+pub fn normalize_events(events: &mut [Event]) {
+    for event in events {
+        event.date = None;
+        normalize_dates(&mut event.row_data);
+    }
+}
+
+/// Shuffle the events with a seeded generator. the same seed always gives
+/// the same order
+pub fn shuffle_events(events: &mut [Event], seed: u64) {
+    // xorshift64, the state must not be zero
+    let mut state = seed | 1;
+    for i in (1..events.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = usize::try_from(state % (i as u64 + 1)).unwrap_or(0);
+        events.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod test_testing {
+
+    use std::collections::HashMap;
+
+    use insta::assert_debug_snapshot;
+    use serde_json::{json, Value};
+
+    use super::{normalize_dates, shuffle_events};
+    use crate::data::{Event, EventKind};
+
+    #[test]
+    fn can_normalize_dates() {
+        let mut value = json!({
+            "title": "pr 1",
+            "updated_at": "2022-10-20T10:00:00Z",
+            "reviews": [{"submitted_at": "2022-10-20T11:00:00Z"}],
+        });
+        normalize_dates(&mut value);
+        assert_debug_snapshot!(value);
+    }
+
+    #[test]
+    fn can_shuffle_events_with_seed() {
+        let events = (0..10)
+            .map(|id| Event {
+                kind: EventKind::Edited,
+                id: id.to_string(),
+                parent_event_id: None,
+                name: String::new(),
+                link: None,
+                date: None,
+                priority: 1,
+                row_data: Value::Null,
+                annotations: HashMap::new(),
+            })
+            .collect::<Vec<_>>();
+        let ids = |seed| {
+            let mut events = events.clone();
+            shuffle_events(&mut events, seed);
+            events.into_iter().map(|e| e.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(42), ids(42));
+        assert_ne!(ids(42), ids(7));
+    }
+}
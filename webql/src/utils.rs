@@ -0,0 +1,282 @@
+//! Shared helpers
+use std::{collections::HashMap, env};
+
+use anyhow::{bail, Context, Result};
+use chrono::Duration;
+
+/// Printed instead of secrets in [`std::fmt::Debug`] implementations
+#[cfg(feature = "github")]
+pub const REDACTED: &str = "***";
+
+/// Prefix of placeholders that are read only from environment variables
+const ENV_PREFIX: &str = "env:";
+
+/// Parse a short duration string like `30s`, `5m`, `12h`, `7d` or `2w` to
+/// [`Duration`]
+///
+/// # Errors
+/// - When the amount is not a number or the unit is unknown
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let unit_at = value
+        .find(|c: char| !c.is_ascii_digit())
+        .with_context(|| format!("duration {} is missing a unit", value))?;
+    let (amount, unit) = value.split_at(unit_at);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("could not parse duration amount: {}", value))?;
+
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => bail!("unknown duration unit {} in {}", unit, value),
+    }
+}
+
+/// Format the [`Duration`] with the largest unit of [`parse_duration`] that
+/// divides it, e.g. `90m` or `2w`
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds();
+    let (amount, unit) = [(604_800, "w"), (86_400, "d"), (3_600, "h"), (60, "m")]
+        .into_iter()
+        .find(|(size, _)| seconds != 0 && seconds % size == 0)
+        .map_or((seconds, "s"), |(size, unit)| (seconds / size, unit));
+    format!("{}{}", amount, unit)
+}
+
+/// Replace the `${NAME}` placeholders in the text. `NAME` is resolved from
+/// the given variables and then from the environment variables,
+/// `${env:NAME}` is resolved only from the environment variables
+///
+/// # Arguments
+/// * `text` - Text with placeholders
+/// * `variables` - User provided variables
+///
+/// # Errors
+/// - When a placeholder is not closed
+/// - When a placeholder could not be resolved
+pub fn substitute_variables(text: &str, variables: &HashMap<String, String>) -> Result<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..];
+        let end = placeholder
+            .find('}')
+            .with_context(|| format!("placeholder is not closed in: {}", text))?;
+        let name = &placeholder[..end];
+
+        let value = match name.strip_prefix(ENV_PREFIX) {
+            Some(env_name) => env::var(env_name).ok(),
+            None => variables.get(name).cloned().or_else(|| env::var(name).ok()),
+        };
+        match value {
+            Some(value) => result.push_str(&value),
+            None => bail!("could not resolve variable {} in: {}", name, text),
+        }
+        rest = &placeholder[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Match a path against a glob pattern. `*` matches any characters in a
+/// path segment, `?` matches a single character and `**` matches any number
+/// of segments, e.g. `crates/webql/**` or `**/*.md`
+///
+/// # Arguments
+/// * `pattern` - Glob pattern
+/// * `path` - `/` separated path
+#[cfg(feature = "github")]
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.split('/').collect::<Vec<_>>();
+    let path = path.split('/').collect::<Vec<_>>();
+    match_segments(&pattern, &path)
+}
+
+/// Match the path segments, `**` can skip any number of segments
+#[cfg(feature = "github")]
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((segment, rest)) => path
+            .split_first()
+            .is_some_and(|(name, path)| match_segment(segment, name) && match_segments(rest, path)),
+    }
+}
+
+/// Match a single path segment with `*` and `?` wildcards
+#[cfg(feature = "github")]
+fn match_segment(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Decode `%XX` escapes of a URL component. `+` is kept as is
+///
+/// # Errors
+/// - When an escape is not two hex digits or the result is not UTF-8
+pub fn url_decode(text: &str) -> Result<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .with_context(|| format!("invalid escape at position {} of `{}`", i, text))?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).context("url decoded text is not UTF-8")
+}
+
+/// Decode standard or URL safe base64, padding and whitespace are ignored
+///
+/// # Errors
+/// - When the text has a character outside of the base64 alphabet
+pub fn base64_decode(text: &str) -> Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let sextet = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' | '-' => 62,
+            '/' | '_' => 63,
+            _ => bail!("invalid base64 character `{}`", c),
+        };
+        buffer = (buffer << 6) | sextet;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod test_utils {
+
+    use std::{collections::HashMap, env};
+
+    use chrono::Duration;
+
+    #[cfg(feature = "github")]
+    use super::glob_match;
+    use super::{base64_decode, format_duration, parse_duration, substitute_variables, url_decode};
+
+    #[test]
+    fn can_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::seconds(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::minutes(5));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7y").is_err());
+    }
+
+    #[test]
+    fn can_format_duration() {
+        for duration in ["30s", "90m", "12h", "6d", "2w", "0s"] {
+            assert_eq!(format_duration(parse_duration(duration).unwrap()), duration);
+        }
+    }
+
+    #[test]
+    fn can_substitute_variables() {
+        env::set_var("WEBQL_TEST_TEAM_LABEL", "team-rust");
+        let variables = HashMap::from([("GITHUB_USER".to_string(), "kaplanelad".to_string())]);
+
+        assert_eq!(
+            substitute_variables("${GITHUB_USER}", &variables).unwrap(),
+            "kaplanelad"
+        );
+        assert_eq!(
+            substitute_variables(
+                "label/${env:WEBQL_TEST_TEAM_LABEL}/${GITHUB_USER}",
+                &variables
+            )
+            .unwrap(),
+            "label/team-rust/kaplanelad"
+        );
+        assert_eq!(
+            substitute_variables("${WEBQL_TEST_TEAM_LABEL}", &variables).unwrap(),
+            "team-rust"
+        );
+        assert_eq!(
+            substitute_variables("no placeholders", &variables).unwrap(),
+            "no placeholders"
+        );
+        assert!(substitute_variables("${env:GITHUB_USER_NOT_FOUND}", &variables).is_err());
+        assert!(substitute_variables("${GITHUB_USER", &variables).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "github")]
+    fn can_match_glob() {
+        assert!(glob_match("crates/webql/**", "crates/webql/src/lib.rs"));
+        assert!(!glob_match("crates/webql/**", "crates/other/src/lib.rs"));
+        assert!(glob_match("**/*.md", "README.md"));
+        assert!(glob_match("**/*.md", "docs/guide/setup.md"));
+        assert!(glob_match("src/*.rs", "src/lib.rs"));
+        assert!(!glob_match("src/*.rs", "src/vendor/mod.rs"));
+        assert!(glob_match("src/l?b.rs", "src/lib.rs"));
+    }
+
+    #[test]
+    fn can_decode_url() {
+        assert_eq!(
+            url_decode("refs%2Fheads%2Fmain%20branch").unwrap(),
+            "refs/heads/main branch"
+        );
+        assert_eq!(url_decode("caf%C3%A9").unwrap(), "café");
+        assert!(url_decode("100%").is_err());
+        assert!(url_decode("%zz").is_err());
+    }
+
+    #[test]
+    fn can_decode_base64() {
+        assert_eq!(base64_decode("d2VicWw=").unwrap(), b"webql");
+        assert_eq!(base64_decode("d2VicQ").unwrap(), b"webq");
+        assert_eq!(base64_decode("aGk/Pz8_").unwrap(), b"hi????");
+        assert!(base64_decode("d2V*").is_err());
+    }
+}
@@ -13,6 +13,19 @@ use fsx::dir::CopyOptions;
 use glob::glob;
 
 const TEMPLATE_PROJECT_NAME: &str = "webql";
+/// Features that are checked one by one by the `features` command
+const FEATURES: &[&str] = &[
+    "async",
+    "device-flow",
+    "edits",
+    "github",
+    "jql",
+    "rayon",
+    "sqlite",
+    "test-util",
+    "webhook",
+    "yaml",
+];
 
 #[allow(clippy::too_many_lines)]
 fn main() -> Result<(), anyhow::Error> {
@@ -34,6 +47,7 @@ fn main() -> Result<(), anyhow::Error> {
                     .multiple_values(true),
             ),
         )
+        .subcommand(Command::new("features"))
         .subcommand(Command::new("fmt"))
         .subcommand(Command::new("clippy"))
         .subcommand(
@@ -125,6 +139,56 @@ fn main() -> Result<(), anyhow::Error> {
             cmd("cargo", &args).run()?;
             Ok(())
         }
+        Some(("features", _)) => {
+            // each feature must build on its own, so consumers can pull only
+            // what they need. the tests are linted too, so feature gated test
+            // code can't break unnoticed
+            println!("=== linting without default features ===");
+            cmd!(
+                "cargo",
+                "clippy",
+                "-p",
+                TEMPLATE_PROJECT_NAME,
+                "--tests",
+                "--no-default-features",
+                "--",
+                "-D",
+                "warnings"
+            )
+            .run()?;
+            for feature in FEATURES {
+                println!("=== linting feature {} ===", feature);
+                cmd!(
+                    "cargo",
+                    "clippy",
+                    "-p",
+                    TEMPLATE_PROJECT_NAME,
+                    "--tests",
+                    "--no-default-features",
+                    "--features",
+                    feature,
+                    "--",
+                    "-D",
+                    "warnings"
+                )
+                .run()?;
+            }
+            println!("=== linting all features ===");
+            cmd!(
+                "cargo",
+                "clippy",
+                "-p",
+                TEMPLATE_PROJECT_NAME,
+                "--tests",
+                "--all-features",
+                "--",
+                "-D",
+                "warnings"
+            )
+            .run()?;
+            println!("ok.");
+            Ok(())
+        }
         Some(("fmt", _)) => {
             cmd!("cargo", "fmt", "--all", "--", "--check").run()?;
             Ok(())